@@ -1,17 +1,47 @@
+use std::collections::HashMap;
+
 use axum::Json;
 use axum::body::Body;
 use axum::extract::{Multipart, Path, Query, State};
 use axum::http::StatusCode;
-use axum::response::Response;
+use axum::http::header::ACCEPT;
+use axum::http::{HeaderMap, Uri};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
 use axum_extra::extract::CookieJar;
-use image::{GenericImageView, ImageFormat};
-use scraper::{Html, Selector};
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use uuid::Uuid;
 
-use crate::bookmark::{BackgroundImage, BookmarkProfile, ImageOrientation};
+use crate::avatar::{self, AvatarFont, AvatarOptions, AvatarShape};
+use crate::background::{self, VariantFormat, remove_background_files};
+use crate::bookmark::{BackgroundImage, Bookmark, BookmarkProfile, IconSource, ImageOrientation};
 use crate::error::{Error, Result};
-use crate::state::AppState;
+use crate::events::EventKind;
+use crate::favicon;
+use crate::gc::{self, GcReport};
+use crate::health::{HealthState, TargetHealth};
+use crate::history::{Actor, HistoryAction, Revision};
+use crate::icon::{
+    IconFetchStatus, accepts_auto_icon, icon_file, icons_to_fetch, remove_icon_file, replace_icon,
+};
+use crate::icon_library::{LIBRARY_ICON_PREFIX, LibraryIcon};
+use crate::keyword;
+use crate::links::{self, LinkAction, LinkReport, LinkStatus};
+use crate::network::{self, ClientInfo};
+use crate::rewrite::{RewriteRule, Rewriter};
+use crate::rotation;
+use crate::search;
+use crate::search_history::{SearchEntry, SearchOwner};
+use crate::state::{AppState, Storage};
+use crate::suggest::SuggestItem;
+use crate::trash::{self, TrashEntry, TrashItem};
+use crate::undo::{self, Operation, OperationKind, UndoSession, UndoState};
+use crate::upload;
+use crate::usage::OpenRecord;
 
 #[derive(Debug, Deserialize)]
 pub struct OptionalProfileQuery {
@@ -71,8 +101,13 @@ pub async fn login(
 }
 
 /// 登出接口
-pub async fn logout(State(app_state): State<AppState>, jar: CookieJar) -> Result<Response> {
+pub async fn logout(
+    State(app_state): State<AppState>,
+    jar: CookieJar,
+    session: UndoSession,
+) -> Result<Response> {
     if let Some(session_id) = jar.get("session_id") {
+        app_state.undo.forget(&session).await;
         app_state
             .storage
             .lock()
@@ -89,35 +124,102 @@ pub async fn logout(State(app_state): State<AppState>, jar: CookieJar) -> Result
     Ok(response)
 }
 
+#[derive(Debug, Serialize)]
+pub struct ProfileResponse {
+    #[serde(flatten)]
+    pub profile: BookmarkProfile,
+    /// Zones matched by `network_rules` in order, the client checks `intranet_check_url` if missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_zones: Option<Vec<String>>,
+}
+
 pub async fn get_profile(
     State(app_state): State<AppState>,
     Query(params): Query<OptionalProfileQuery>,
-) -> Result<Json<BookmarkProfile>> {
+    client: ClientInfo,
+    owner: SearchOwner,
+) -> Result<Json<ProfileResponse>> {
     let mut storage = app_state.storage.lock().await;
-    if let Some(name) = params.profile.as_deref()
+    let profile = if let Some(name) = params.profile.as_deref()
         && let Some(profile) = storage.get_profile(name)
     {
-        Ok(Json(profile.clone()))
+        profile.clone()
     } else {
-        Ok(Json(storage.get_default_profile().await.clone()))
+        storage.get_default_profile().await.clone()
+    };
+
+    let mut profile = profile;
+    // 已保存的规则在更新时验证过
+    if let Ok(rewriter) = Rewriter::new(&profile.url_rewrites) {
+        for bookmark in &mut profile.bookmarks {
+            bookmark.derived_urls = rewriter.derived_urls(bookmark);
+        }
+    }
+    let choices = bookmark_choices(&storage, &owner);
+    for bookmark in &mut profile.bookmarks {
+        bookmark.stats = storage.usage.stats(bookmark.id).map(|mut stats| {
+            stats.searches = choices.get(&bookmark.id).copied().unwrap_or_default();
+            stats
+        });
     }
+
+    let network_zones = profile
+        .network_rules
+        .as_ref()
+        .and_then(|rules| network::resolve_zones(rules, &client));
+    Ok(Json(ProfileResponse {
+        profile,
+        network_zones,
+    }))
 }
 
 pub async fn create_profile(
     State(app_state): State<AppState>,
+    actor: Actor,
+    session: UndoSession,
     Json(payload): Json<BookmarkProfile>,
 ) -> Result<()> {
     let mut storage = app_state.storage.lock().await;
     if storage.profiles.iter().any(|p| p.name == payload.name) {
         return Err(Error::ProfileAlreadyExists);
     }
+    Rewriter::new(&payload.url_rewrites)?;
+    search::validate_engines(&payload.search_engines)?;
+    keyword::check_keywords(&storage.profiles, &payload)?;
+    let fetch_icons = if storage.config.icon.auto_fetch {
+        icons_to_fetch(&[], &payload.bookmarks)
+    } else {
+        vec![]
+    };
+    let name = payload.name.clone();
+    let index = storage.profiles.len();
     storage.profiles.push(payload);
+    // 背景图通过 id 引用图库中的图片
+    storage.sync_background_images();
     storage.save_profiles().await?;
+    storage.record_history(actor, HistoryAction::Create, None, Some(&name));
+    if let Some(profile) = storage.get_profile(&name).cloned() {
+        let operation = Operation::Create {
+            profile,
+            index,
+            trash_id: None,
+        };
+        push_undo(&app_state, &storage, session, operation).await;
+    }
+    drop(storage);
+
+    app_state
+        .events
+        .publish(EventKind::Profile, None, json!({ "created": name }));
+
+    app_state.icon_jobs.enqueue(fetch_icons).await;
     Ok(())
 }
 
 pub async fn delete_profile(
     State(app_state): State<AppState>,
+    actor: Actor,
+    session: UndoSession,
     Query(params): Query<ProfileQuery>,
 ) -> Result<()> {
     let mut storage = app_state.storage.lock().await;
@@ -125,23 +227,527 @@ pub async fn delete_profile(
         return Err(Error::CannotDeleteLastProfile);
     }
     let profile_name = params.profile;
-    storage.profiles.retain(|p| p.name != profile_name);
+    let Some(index) = storage.profiles.iter().position(|p| p.name == profile_name) else {
+        return Err(Error::ProfileNotFound);
+    };
+    let before = storage.profiles.remove(index);
+    // 移到回收站，背景图随 profile 一起保留，彻底删除后文件由 GC 清理
+    let entry = TrashEntry::new(TrashItem::Profile {
+        profile: before.clone(),
+        index,
+    });
+    let operation = Operation::Delete {
+        profile: before.clone(),
+        index,
+        trash_id: Some(entry.id),
+    };
+    storage.trash.push(entry);
+    storage.prune_images();
     storage.save_profiles().await?;
+    storage.save_images().await?;
+    storage.save_trash().await?;
+    let before = Some(before);
+    storage.record_history(actor, HistoryAction::Delete, before.as_ref(), None);
+    push_undo(&app_state, &storage, session, operation).await;
+    drop(storage);
+
+    app_state
+        .events
+        .publish(EventKind::Profile, None, json!({ "deleted": profile_name }));
     Ok(())
 }
 
 pub async fn update_profile(
     State(app_state): State<AppState>,
-    Json(payload): Json<BookmarkProfile>,
+    actor: Actor,
+    session: UndoSession,
+    Json(mut payload): Json<BookmarkProfile>,
 ) -> Result<()> {
+    Rewriter::new(&payload.url_rewrites)?;
+    search::validate_engines(&payload.search_engines)?;
     let mut storage = app_state.storage.lock().await;
+    keyword::check_keywords(&storage.profiles, &payload)?;
+    let auto_fetch = storage.config.icon.auto_fetch;
+    let mut fetch_icons = vec![];
+    let mut bookmarks_changed = false;
+    let name = payload.name.clone();
+    let before = storage.get_profile(&name).cloned();
     if let Some(profile) = storage.get_profile_mut(&payload.name) {
+        // 保留后台自动获取的图标，避免被旧数据覆盖
+        for bookmark in &mut payload.bookmarks {
+            if bookmark.icon.is_none()
+                && let Some(old) = profile.bookmarks.iter().find(|b| b.id == bookmark.id)
+                && old.url == bookmark.url
+                && old.icon_source == IconSource::Fetched
+            {
+                bookmark.icon = old.icon.clone();
+                bookmark.icon_source = IconSource::Fetched;
+            }
+        }
+        if auto_fetch {
+            fetch_icons = icons_to_fetch(&profile.bookmarks, &payload.bookmarks);
+        }
+        bookmarks_changed = serde_json::to_value(&profile.bookmarks).ok()
+            != serde_json::to_value(&payload.bookmarks).ok();
         *profile = payload;
     }
+    storage.sync_background_images();
+    if let Some(before) = &before {
+        storage.trash_removed_bookmarks(before);
+    }
+    // 重新加入的书签不再留在回收站中
+    storage.untrash_bookmarks(&name);
+    storage.trash_unused_images(&name);
     storage.save_profiles().await?;
+    storage.save_images().await?;
+    storage.save_trash().await?;
+    if let Some(before) = before
+        && let Some(after) = storage.get_profile(&name).cloned()
+    {
+        storage.record_history(actor, HistoryAction::Update, Some(&before), Some(&name));
+        // 没有修改的保存不占用撤销记录
+        if !undo::same_profile(&after, &before) {
+            push_undo(
+                &app_state,
+                &storage,
+                session,
+                Operation::Update {
+                    before: Box::new(before),
+                    after: Box::new(after),
+                },
+            )
+            .await;
+        }
+    }
+    drop(storage);
+
+    let kind = if bookmarks_changed {
+        EventKind::Bookmark
+    } else {
+        EventKind::Profile
+    };
+    app_state.events.publish(kind, Some(&name), json!({}));
+
+    app_state.icon_jobs.enqueue(fetch_icons).await;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GoQuery {
+    /// The zone chosen by the client, empty for `url`. Decided by the network rules if missing.
+    pub zone: Option<String>,
+    /// The search the bookmark was chosen from, recorded in the search history.
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GoPath {
+    /// A bookmark id or keyword.
+    pub target: String,
+}
+
+/// 记录书签的打开次数，然后跳转到当前网络区域对应的链接
+///
+/// 通过书签 ID 打开时可以用 `zone` 参数指定区域；通过关键字打开时由网络规则决定区域，
+/// `/go/{keyword}/` 之后的路径和查询参数会传给书签链接
+pub async fn go(
+    State(app_state): State<AppState>,
+    Path(path): Path<GoPath>,
+    Query(query): Query<GoQuery>,
+    uri: Uri,
+    client: ClientInfo,
+    owner: SearchOwner,
+) -> Result<Response> {
+    let mut storage = app_state.storage.lock().await;
+    let Some((profile, bookmark)) = keyword::find(&storage.profiles, &path.target) else {
+        return Err(Error::BookmarkNotFound);
+    };
+    let by_id = Uuid::parse_str(&path.target).is_ok();
+    let zone = if by_id { query.zone } else { None };
+    let (zone, url) = bookmark_target(profile, bookmark, zone, &client);
+    // 使用请求中的原始编码，`Path` 提取的剩余路径已经解码
+    let rest = uri.path().splitn(4, '/').nth(3).unwrap_or_default();
+    let url = if by_id {
+        keyword::expand(&url, rest, None)
+    } else {
+        keyword::expand(&url, rest, uri.query())
+    };
+    let id = bookmark.id;
+    let profile = profile.name.clone();
+    // 只有通过书签 ID 打开时 `q` 才是搜索内容，关键字链接的查询参数属于书签链接
+    if by_id && let Some(q) = query.q.as_deref() {
+        let entry = SearchEntry::new(q, profile.clone(), None, Some(id));
+        record_search(&mut storage, &owner, entry)?;
+    }
+    record_open(&mut storage, id, zone, profile)?;
+
+    Ok(redirect(&url))
+}
+
+fn record_open(
+    storage: &mut Storage,
+    id: Uuid,
+    zone: Option<String>,
+    profile: String,
+) -> Result<()> {
+    storage.usage.record(
+        id,
+        OpenRecord {
+            at: Utc::now(),
+            zone,
+            profile,
+        },
+    );
+    storage.usage.save()?;
+    Ok(())
+}
+
+/// 记录到搜索历史，关闭搜索历史时不记录
+fn record_search(storage: &mut Storage, owner: &SearchOwner, entry: SearchEntry) -> Result<()> {
+    let config = storage.config.search_history.clone();
+    if !config.enabled {
+        return Ok(());
+    }
+    storage.search_history.record(&config, owner, entry);
+    storage.search_history.save()?;
+    Ok(())
+}
+
+/// 当前用户在搜索中选择各书签的次数，关闭搜索历史时为空
+fn bookmark_choices(storage: &Storage, owner: &SearchOwner) -> HashMap<Uuid, u64> {
+    if !storage.config.search_history.enabled {
+        return HashMap::new();
+    }
+    storage.search_history.bookmark_choices(owner)
+}
+
+/// 书签建议的排序分数：使用频率加上在搜索中被选择的次数，每次选择相当于一次最近的打开
+fn suggestion_rank<'a>(storage: &'a Storage, owner: &SearchOwner) -> impl Fn(Uuid) -> f64 + 'a {
+    let choices = bookmark_choices(storage, owner);
+    move |id| {
+        let frecency = storage.usage.stats(id).map_or(0.0, |s| s.frecency);
+        frecency + choices.get(&id).copied().unwrap_or_default() as f64 * 100.0
+    }
+}
+
+/// 选择书签在客户端所在网络区域的链接，返回使用的区域和链接
+fn bookmark_target(
+    profile: &BookmarkProfile,
+    bookmark: &Bookmark,
+    zone: Option<String>,
+    client: &ClientInfo,
+) -> (Option<String>, String) {
+    let rewriter = Rewriter::new(&profile.url_rewrites).ok();
+    let url_for = |zone: &str| match &rewriter {
+        Some(rewriter) => rewriter.url_for(bookmark, zone),
+        None => bookmark.zone_url(zone).map(str::to_string),
+    };
+    let zones = match zone {
+        Some(zone) if zone.is_empty() => vec![],
+        Some(zone) => vec![zone],
+        None => profile
+            .network_rules
+            .as_ref()
+            .and_then(|rules| network::resolve_zones(rules, client))
+            .unwrap_or_default(),
+    };
+    zones
+        .into_iter()
+        .find_map(|zone| url_for(&zone).map(|url| (Some(zone), url)))
+        .unwrap_or_else(|| (None, bookmark.url.clone()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchRedirectQuery {
+    pub q: String,
+    /// The profile whose search engines are used, the first profile if missing.
+    pub profile: Option<String>,
+}
+
+/// 解析搜索内容中的快捷方式、书签关键字和标题，浏览器直接跳转，请求 JSON 时返回解析结果
+pub async fn search_redirect(
+    State(app_state): State<AppState>,
+    Query(query): Query<SearchRedirectQuery>,
+    headers: HeaderMap,
+    client: ClientInfo,
+    owner: SearchOwner,
+) -> Result<Response> {
+    if query.q.trim().is_empty() {
+        return Err(Error::BadRequest);
+    }
+    let wants_json = headers
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"));
+
+    let mut storage = app_state.storage.lock().await;
+    let profile = search_profile(&storage, query.profile.as_deref())?;
+    let Some(mut target) = search::resolve(&storage.profiles, profile, &query.q) else {
+        return Err(Error::InvalidSearchEngine("no search engine".to_string()));
+    };
+    let profile = profile.name.clone();
+    // 打开书签时和 /go 一样选择网络区域的链接并记录使用次数
+    if target.opens_bookmark()
+        && let Some(id) = target.bookmark
+        && let Some((profile, bookmark)) = keyword::find(&storage.profiles, &id.to_string())
+    {
+        let (zone, url) = bookmark_target(profile, bookmark, None, &client);
+        target.url = url;
+        if !wants_json {
+            let profile = profile.name.clone();
+            record_open(&mut storage, id, zone, profile)?;
+        }
+    }
+    if wants_json {
+        return Ok(Json(target).into_response());
+    }
+    let entry = SearchEntry::new(&query.q, profile, target.engine.clone(), target.bookmark);
+    record_search(&mut storage, &owner, entry)?;
+    drop(storage);
+
+    Ok(redirect(&target.url))
+}
+
+/// 指定的 profile，没有指定时使用第一个
+fn search_profile<'a>(storage: &'a Storage, name: Option<&str>) -> Result<&'a BookmarkProfile> {
+    match name {
+        Some(name) => storage.get_profile(name).ok_or(Error::ProfileNotFound),
+        None => storage.profiles.first().ok_or(Error::ProfileNotFound),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenSearchQuery {
+    pub profile: Option<String>,
+}
+
+/// OpenSearch 描述文件，让浏览器把导航页添加为搜索引擎
+pub async fn opensearch(
+    State(app_state): State<AppState>,
+    Query(query): Query<OpenSearchQuery>,
+    client: ClientInfo,
+) -> Result<Response> {
+    let storage = app_state.storage.lock().await;
+    let profile = search_profile(&storage, query.profile.as_deref())?;
+    let rules = profile.network_rules.clone().unwrap_or_default();
+    let Some(origin) = client.origin(&rules) else {
+        return Err(Error::BadRequest);
+    };
+    let xml = search::opensearch_description(&origin, profile);
+    drop(storage);
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/opensearchdescription+xml")
+        .body(Body::from(xml))
+        .unwrap())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchSuggestionsQuery {
+    #[serde(default)]
+    pub q: String,
+    pub profile: Option<String>,
+}
+
+/// OpenSearch 格式的搜索建议：`[搜索内容, [补全], [说明], [链接]]`
+///
+/// 建议包含书签的标题和链接，只返回给已登录的浏览器
+pub async fn search_suggestions(
+    State(app_state): State<AppState>,
+    Query(query): Query<SearchSuggestionsQuery>,
+    client: ClientInfo,
+    owner: SearchOwner,
+) -> Result<Response> {
+    let storage = app_state.storage.lock().await;
+    let profile = search_profile(&storage, query.profile.as_deref())?;
+    let rank = suggestion_rank(&storage, &owner);
+    let mut suggestions = search::suggest(profile, &query.q, rank, 8);
+    let rules = profile.network_rules.clone().unwrap_or_default();
+    let origin = client.origin(&rules).unwrap_or_default();
+    let suggest_url =
+        search::default_engine(&profile.search_engines).and_then(|e| e.suggest_url.clone());
+    drop(storage);
+
+    // 书签不够时用默认搜索引擎的建议补充
+    if let Some(suggest_url) = suggest_url
+        && suggestions.len() < 8
+        && !query.q.trim().is_empty()
+    {
+        let engine = app_state
+            .suggestions
+            .engine_suggestions(&suggest_url, query.q.trim())
+            .await;
+        let remaining = 8 - suggestions.len();
+        suggestions.extend(
+            engine
+                .into_iter()
+                .take(remaining)
+                .map(|text| search::Suggestion {
+                    completion: text,
+                    description: String::new(),
+                    bookmark: None,
+                }),
+        );
+    }
+
+    let completions: Vec<&str> = suggestions.iter().map(|s| s.completion.as_str()).collect();
+    let descriptions: Vec<&str> = suggestions.iter().map(|s| s.description.as_str()).collect();
+    // 带上搜索内容，选择书签也会记录到搜索历史
+    let q = search::encode(query.q.trim());
+    let urls: Vec<String> = suggestions
+        .iter()
+        .map(|s| match s.bookmark {
+            Some(id) => format!("{origin}/go/{id}?q={q}"),
+            None => String::new(),
+        })
+        .collect();
+    let body = json!([query.q, completions, descriptions, urls]);
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/x-suggestions+json")
+        .body(Body::from(body.to_string()))
+        .unwrap())
+}
+
+fn redirect(url: &str) -> Response {
+    Response::builder()
+        .status(StatusCode::FOUND)
+        .header("Location", url)
+        .header("Cache-Control", "no-store")
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchSuggestQuery {
+    #[serde(default)]
+    pub q: String,
+    pub profile: Option<String>,
+    /// Name or shortcut of the engine asked for suggestions, the default engine if missing.
+    pub engine: Option<String>,
+}
+
+/// 搜索框的建议：匹配的书签、最近的搜索和搜索引擎的建议，按顺序去重
+pub async fn search_suggest(
+    State(app_state): State<AppState>,
+    Query(query): Query<SearchSuggestQuery>,
+    owner: SearchOwner,
+) -> Result<Json<Vec<SuggestItem>>> {
+    if query.q.trim().is_empty() {
+        return Ok(Json(vec![]));
+    }
+    let storage = app_state.storage.lock().await;
+    let profile = search_profile(&storage, query.profile.as_deref())?;
+    let engine = match &query.engine {
+        Some(name) => Some(
+            search::find_engine(&profile.search_engines, name)
+                .ok_or(Error::InvalidSearchEngine(name.clone()))?,
+        ),
+        None => search::default_engine(&profile.search_engines),
+    };
+    let suggest_url = engine.and_then(|e| e.suggest_url.clone());
+    let bookmarks = search::suggest(profile, &query.q, suggestion_rank(&storage, &owner), 5);
+    let recent = if storage.config.search_history.enabled {
+        storage.search_history.recent_queries(&owner, &query.q)
+    } else {
+        vec![]
+    };
+    drop(storage);
+
+    let mut items: Vec<SuggestItem> = bookmarks
+        .into_iter()
+        .filter_map(|s| {
+            Some(SuggestItem::Bookmark {
+                text: s.completion,
+                title: s.description,
+                id: s.bookmark?,
+            })
+        })
+        .collect();
+    items.extend(
+        recent
+            .into_iter()
+            .take(3)
+            .map(|text| SuggestItem::Recent { text }),
+    );
+    if let Some(suggest_url) = suggest_url {
+        let suggestions = app_state
+            .suggestions
+            .engine_suggestions(&suggest_url, query.q.trim())
+            .await;
+        items.extend(
+            suggestions
+                .into_iter()
+                .map(|text| SuggestItem::Engine { text }),
+        );
+    }
+
+    let mut seen = vec![];
+    items.retain(|item| {
+        let text = item.text().to_lowercase();
+        if seen.contains(&text) {
+            return false;
+        }
+        seen.push(text);
+        true
+    });
+    items.truncate(12);
+    Ok(Json(items))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHistoryResponse {
+    pub enabled: bool,
+    /// Entries older than this are removed, 0 when they are kept forever.
+    pub retention_days: u64,
+    /// Newest first.
+    pub entries: Vec<SearchEntry>,
+}
+
+/// 当前用户（未登录时为当前设备）的搜索历史
+pub async fn get_search_history(
+    State(app_state): State<AppState>,
+    owner: SearchOwner,
+) -> Result<Json<SearchHistoryResponse>> {
+    let storage = app_state.storage.lock().await;
+    let config = &storage.config.search_history;
+    let entries = if config.enabled {
+        storage.search_history.entries(&owner).cloned().collect()
+    } else {
+        vec![]
+    };
+    Ok(Json(SearchHistoryResponse {
+        enabled: config.enabled,
+        retention_days: config.retention_days,
+        entries,
+    }))
+}
+
+/// 删除一条搜索历史
+pub async fn delete_search_history_entry(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    owner: SearchOwner,
+) -> Result<()> {
+    let mut storage = app_state.storage.lock().await;
+    if !storage.search_history.remove(&owner, id) {
+        return Err(Error::BadRequest);
+    }
+    storage.search_history.save()?;
     Ok(())
 }
 
+/// 清空当前用户的搜索历史，返回删除的条目数量
+pub async fn clear_search_history(
+    State(app_state): State<AppState>,
+    owner: SearchOwner,
+) -> Result<Json<usize>> {
+    let mut storage = app_state.storage.lock().await;
+    let cleared = storage.search_history.clear(&owner);
+    storage.search_history.save()?;
+    Ok(Json(cleared))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RenameProfileRequest {
     pub name: String,
@@ -150,19 +756,246 @@ pub struct RenameProfileRequest {
 
 pub async fn rename_profile(
     State(app_state): State<AppState>,
+    actor: Actor,
+    session: UndoSession,
     Json(payload): Json<RenameProfileRequest>,
 ) -> Result<()> {
     let mut storage = app_state.storage.lock().await;
     if storage.profiles.iter().any(|p| p.name == payload.new_name) {
         return Err(Error::ProfileAlreadyExists);
     }
-    if let Some(profile) = storage.get_profile_mut(&payload.name) {
-        profile.name = payload.new_name;
+    let Some(profile) = storage.get_profile_mut(&payload.name) else {
+        return Err(Error::ProfileNotFound);
+    };
+    let before = profile.clone();
+    profile.name = payload.new_name.clone();
+    storage.save_profiles().await?;
+    // 历史记录跟随新名称，才能恢复改名前的版本
+    if let Err(err) = storage
+        .history
+        .rename_profile(&payload.name, &payload.new_name)
+    {
+        tracing::warn!("Failed to rename history: {err}");
+    }
+    storage.record_history(
+        actor,
+        HistoryAction::Rename,
+        Some(&before),
+        Some(&payload.new_name),
+    );
+    let operation = Operation::Rename {
+        from: payload.name.clone(),
+        to: payload.new_name.clone(),
+    };
+    push_undo(&app_state, &storage, session, operation).await;
+    drop(storage);
+
+    app_state.events.publish(
+        EventKind::Profile,
+        None,
+        json!({ "renamed": payload.name, "new_name": payload.new_name }),
+    );
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub profile: String,
+    /// Only revisions changing this bookmark.
+    pub bookmark: Option<Uuid>,
+    pub limit: Option<usize>,
+}
+
+/// 获取 profile 的修改历史，最新的在前
+pub async fn get_profile_history(
+    State(app_state): State<AppState>,
+    Query(params): Query<HistoryQuery>,
+) -> Result<Json<Vec<Revision>>> {
+    let storage = app_state.storage.lock().await;
+    let limit = params.limit.unwrap_or(50);
+    Ok(Json(
+        storage
+            .history
+            .revisions(&params.profile)
+            .filter(|r| params.bookmark.is_none_or(|id| r.touches(id)))
+            .take(limit)
+            .cloned()
+            .collect(),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreRequest {
+    pub profile: String,
+    /// Restore the state right after this revision.
+    pub revision: u64,
+    /// Only restore this bookmark, the whole profile if missing.
+    #[serde(default)]
+    pub bookmark: Option<Uuid>,
+}
+
+/// 将 profile 或单个书签恢复到某个版本，恢复本身也会记录到历史中
+pub async fn restore_profile(
+    State(app_state): State<AppState>,
+    actor: Actor,
+    Json(payload): Json<RestoreRequest>,
+) -> Result<()> {
+    let mut storage = app_state.storage.lock().await;
+    let before = storage.get_profile(&payload.profile).cloned();
+    let mut restored = match payload.bookmark {
+        None => storage
+            .history
+            .profile_at(before.as_ref(), &payload.profile, payload.revision)
+            .ok_or(Error::RevisionNotFound)?,
+        Some(id) => {
+            let Some(mut profile) = before.clone() else {
+                return Err(Error::ProfileNotFound);
+            };
+            let index = profile.bookmarks.iter().position(|b| b.id == id);
+            let bookmark = storage
+                .history
+                .bookmark_at(
+                    index.map(|i| &profile.bookmarks[i]),
+                    &payload.profile,
+                    payload.revision,
+                    id,
+                )
+                .ok_or(Error::RevisionNotFound)?;
+            match (index, bookmark) {
+                (Some(index), Some(bookmark)) => profile.bookmarks[index] = bookmark,
+                (Some(index), None) => {
+                    profile.bookmarks.remove(index);
+                }
+                (None, Some(bookmark)) => profile.bookmarks.push(bookmark),
+                (None, None) => {}
+            }
+            profile
+        }
+    };
+
+    keyword::check_keywords(&storage.profiles, &restored)?;
+
+    // 已被 GC 隔离的图标和背景图移回原目录，找不到的图标重新生成
+    for bookmark in &mut restored.bookmarks {
+        if let Some(file) = bookmark.icon.as_deref().and_then(icon_file)
+            && !gc::recover_file(ICON_DIR, "icons", file).await
+        {
+            bookmark.icon = None;
+        }
+    }
+    for bg in &restored.background_images {
+        if storage.images.iter().any(|i| i.id == bg.id) {
+            continue;
+        }
+        let mut recovered = true;
+        for file in bg.files() {
+            recovered &= gc::recover_file(BACKGROUND_DIR, "backgrounds", file).await;
+        }
+        if recovered {
+            storage.images.push(bg.clone());
+        }
+    }
+
+    let fetch_icons = if storage.config.icon.auto_fetch {
+        icons_to_fetch(
+            before
+                .as_ref()
+                .map(|p| p.bookmarks.as_slice())
+                .unwrap_or_default(),
+            &restored.bookmarks,
+        )
+    } else {
+        vec![]
+    };
+    match storage.get_profile_mut(&payload.profile) {
+        Some(profile) => *profile = restored,
+        None => storage.profiles.push(restored),
     }
+    storage.sync_background_images();
+    if let Some(before) = &before {
+        storage.trash_removed_bookmarks(before);
+    }
+    storage.untrash_bookmarks(&payload.profile);
+    storage.trash_unused_images(&payload.profile);
     storage.save_profiles().await?;
+    storage.save_images().await?;
+    storage.save_trash().await?;
+    storage.record_history(
+        actor,
+        HistoryAction::Restore,
+        before.as_ref(),
+        Some(&payload.profile),
+    );
+    drop(storage);
+
+    if before.is_none() {
+        app_state.events.publish(
+            EventKind::Profile,
+            None,
+            json!({ "created": payload.profile }),
+        );
+    } else {
+        app_state
+            .events
+            .publish(EventKind::Bookmark, Some(&payload.profile), json!({}));
+    }
+    app_state.icon_jobs.enqueue(fetch_icons).await;
     Ok(())
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RewritePreviewRequest {
+    /// Rules to try, the saved rules of the profile if missing.
+    #[serde(default)]
+    pub rules: Option<Vec<RewriteRule>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RewritePreview {
+    pub id: Uuid,
+    pub title: String,
+    pub url: String,
+    /// Derived links by zone.
+    pub derived: IndexMap<String, String>,
+    /// Zones in which the bookmark's own link is used instead.
+    pub overridden: Vec<String>,
+}
+
+/// 预览重写规则为每个书签生成的链接
+pub async fn preview_url_rewrites(
+    State(app_state): State<AppState>,
+    Query(params): Query<ProfileQuery>,
+    Json(payload): Json<RewritePreviewRequest>,
+) -> Result<Json<Vec<RewritePreview>>> {
+    let storage = app_state.storage.lock().await;
+    let Some(profile) = storage.get_profile(&params.profile) else {
+        return Err(Error::ProfileNotFound);
+    };
+    let rewriter = Rewriter::new(payload.rules.as_ref().unwrap_or(&profile.url_rewrites))?;
+
+    Ok(Json(
+        profile
+            .bookmarks
+            .iter()
+            .map(|bookmark| {
+                let derived = rewriter.derive(bookmark);
+                let overridden = derived
+                    .keys()
+                    .filter(|zone| bookmark.zone_url(zone).is_some())
+                    .cloned()
+                    .collect();
+                RewritePreview {
+                    id: bookmark.id,
+                    title: bookmark.title.clone(),
+                    url: bookmark.url.clone(),
+                    derived,
+                    overridden,
+                }
+            })
+            .collect(),
+    ))
+}
+
 pub async fn get_all_profile_names(State(app_state): State<AppState>) -> Result<Json<Vec<String>>> {
     let storage = app_state.storage.lock().await;
     Ok(Json(
@@ -172,6 +1005,7 @@ pub async fn get_all_profile_names(State(app_state): State<AppState>) -> Result<
 
 pub async fn sort_profiles(
     State(app_state): State<AppState>,
+    session: UndoSession,
     Json(payload): Json<Vec<String>>,
 ) -> Result<()> {
     let names_order = payload;
@@ -179,6 +1013,8 @@ pub async fn sort_profiles(
     if names_order.len() != storage.profiles.len() {
         return Err(Error::InvalidProfileOrder);
     }
+    let before = storage.profiles.iter().map(|p| p.name.clone()).collect();
+    let after = names_order.clone();
     let mut new_profiles = Vec::new();
     for name in names_order {
         if let Some(profile) = storage.get_profile(&name) {
@@ -189,6 +1025,18 @@ pub async fn sort_profiles(
     }
     storage.profiles = new_profiles;
     storage.save_profiles().await?;
+    push_undo(
+        &app_state,
+        &storage,
+        session,
+        Operation::Sort { before, after },
+    )
+    .await;
+    drop(storage);
+
+    app_state
+        .events
+        .publish(EventKind::Profile, None, json!({ "sorted": true }));
     Ok(())
 }
 
@@ -215,221 +1063,165 @@ pub struct FaviconIcon {
 
 /// 从 URL 获取 favicon
 pub async fn fetch_favicon(Query(query): Query<FetchFaviconQuery>) -> Result<Json<FaviconResult>> {
-    let url = &query.url;
-    let base_url = match url::Url::parse(url) {
-        Ok(parsed) => format!("{}://{}", parsed.scheme(), parsed.host_str().unwrap_or("")),
-        Err(_) => return Err(Error::BadRequest),
-    };
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .build()
-        .map_err(|_| Error::BadRequest)?;
+    let client = favicon::http_client()?;
+    let icons = favicon::fetch_favicons(&client, &query.url)
+        .await?
+        .into_iter()
+        .map(|icon| {
+            use base64::Engine;
+            FaviconIcon {
+                url: icon.url,
+                data: base64::engine::general_purpose::STANDARD.encode(&icon.bytes),
+                content_type: icon.content_type,
+            }
+        })
+        .collect();
 
-    let mut icon_urls: Vec<String> = Vec::new();
+    Ok(Json(FaviconResult { icons }))
+}
 
-    // 1. 尝试解析 HTML 页面获取 link 标签中的图标
-    if let Ok(response) = client.get(url).send().await
-        && let Ok(html) = response.text().await
-    {
-        let document = Html::parse_document(&html);
-
-        // 查找所有可能的图标链接
-        let selectors = [
-            r#"link[rel="icon"]"#,
-            r#"link[rel="shortcut icon"]"#,
-            r#"link[rel="apple-touch-icon"]"#,
-            r#"link[rel="apple-touch-icon-precomposed"]"#,
-            r#"link[rel="mask-icon"]"#,
-        ];
-
-        for selector_str in selectors {
-            if let Ok(selector) = Selector::parse(selector_str) {
-                for element in document.select(&selector) {
-                    if let Some(href) = element.value().attr("href") {
-                        let icon_url = resolve_url(&base_url, url, href);
-                        if !icon_urls.contains(&icon_url) {
-                            icon_urls.push(icon_url);
-                        }
-                    }
-                }
-            }
+pub async fn set_icon(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<()> {
+    let upload_config = {
+        let storage = app_state.storage.lock().await;
+        if storage.get_bookmark(id).is_none() {
+            return Err(Error::BookmarkNotFound);
         }
-    }
+        storage.config.upload.clone()
+    };
 
-    // 2. 添加常见的 favicon 路径
-    let common_paths = [
-        "/favicon.ico",
-        "/favicon.png",
-        "/favicon.svg",
-        "/apple-touch-icon.png",
-        "/apple-touch-icon-precomposed.png",
-        "/favicon-32x32.png",
-        "/favicon-16x16.png",
-    ];
-
-    for path in common_paths {
-        let icon_url = format!("{base_url}{path}");
-        if !icon_urls.contains(&icon_url) {
-            icon_urls.push(icon_url);
-        }
-    }
+    let upload = upload::receive_file(&mut multipart, "icon", upload_config.max_icon_bytes).await?;
+    let format = upload.format().ok_or(Error::InvalidImageFormat)?;
+    upload::check_quota(&upload_config, upload.size).await?;
 
-    // 3. 尝试获取每个图标
-    let mut icons: Vec<FaviconIcon> = Vec::new();
+    let mut storage = app_state.storage.lock().await;
+    let Some(bookmark) = storage.get_bookmark_mut(id) else {
+        return Err(Error::BookmarkNotFound);
+    };
 
-    for icon_url in icon_urls {
-        if let Ok(response) = client.get(&icon_url).send().await
-            && response.status().is_success()
-        {
-            let content_type = response
-                .headers()
-                .get("content-type")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("image/x-icon")
-                .to_string();
-
-            // 检查是否是图片类型
-            if (content_type.starts_with("image/") || content_type.contains("icon"))
-                && let Ok(bytes) = response.bytes().await
-            {
-                // 验证是否是有效的图片
-                if is_valid_image(&bytes) {
-                    use base64::Engine;
-                    let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
-                    icons.push(FaviconIcon {
-                        url: icon_url,
-                        data,
-                        content_type,
-                    });
-                }
-            }
-        }
-    }
+    tokio::fs::create_dir_all(ICON_DIR).await?;
+    let icon_filename = format!("{id}.{}", format.extensions_str()[0]);
+    upload.persist(format!("{ICON_DIR}/{icon_filename}"))?;
+    replace_icon(bookmark, icon_filename, IconSource::Uploaded).await;
+    storage.save_profiles().await?;
+    publish_bookmark_changed(&app_state, &storage, id);
 
-    Ok(Json(FaviconResult { icons }))
+    Ok(())
 }
 
-/// 解析相对 URL 为绝对 URL
-fn resolve_url(base_url: &str, page_url: &str, href: &str) -> String {
-    if href.starts_with("http://") || href.starts_with("https://") {
-        href.to_string()
-    } else if href.starts_with("//") {
-        format!("https:{href}")
-    } else if href.starts_with('/') {
-        format!("{base_url}{href}")
-    } else {
-        // 相对路径
-        if let Ok(base) = url::Url::parse(page_url)
-            && let Ok(resolved) = base.join(href)
-        {
-            return resolved.to_string();
-        }
-        format!("{base_url}/{href}")
-    }
+#[derive(Debug, Deserialize)]
+pub struct GeneratedIconQuery {
+    pub shape: Option<AvatarShape>,
+    pub font: Option<AvatarFont>,
 }
 
-/// 检查字节数据是否是有效的图片
-fn is_valid_image(bytes: &[u8]) -> bool {
-    // 检查常见图片格式的魔数
-    if bytes.len() < 4 {
-        return false;
-    }
+/// 生成书签的字母头像，未指定的选项使用书签中保存的值
+pub async fn get_generated_icon(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<GeneratedIconQuery>,
+) -> Result<Response> {
+    let storage = app_state.storage.lock().await;
+    let Some(bookmark) = storage.get_bookmark(id) else {
+        return Err(Error::BookmarkNotFound);
+    };
 
-    // PNG
-    if bytes.starts_with(&[0x89, 0x50, 0x4E, 0x47]) {
-        return true;
-    }
-    // JPEG
-    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
-        return true;
-    }
-    // GIF
-    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
-        return true;
-    }
-    // ICO
-    if bytes.starts_with(&[0x00, 0x00, 0x01, 0x00]) {
-        return true;
-    }
-    // WebP
-    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
-        return true;
-    }
-    // SVG (text-based, check for xml or svg tag)
-    if let Ok(text) = std::str::from_utf8(&bytes[..bytes.len().min(1000)]) {
-        let text_lower = text.to_lowercase();
-        if text_lower.contains("<svg") || text_lower.contains("<?xml") {
-            return true;
-        }
-    }
-    // BMP
-    if bytes.starts_with(b"BM") {
-        return true;
-    }
+    let stored = bookmark
+        .icon
+        .as_deref()
+        .and_then(AvatarOptions::from_icon)
+        .unwrap_or_default();
+    let options = AvatarOptions {
+        shape: query.shape.unwrap_or(stored.shape),
+        font: query.font.unwrap_or(stored.font),
+    };
+    let svg = avatar::render_svg(&bookmark.title, &bookmark.url, options);
+
+    let response = Response::builder()
+        .header("Content-Type", "image/svg+xml")
+        .header("Cache-Control", "no-cache")
+        .status(StatusCode::OK)
+        .body(Body::from(svg))
+        .unwrap();
 
-    false
+    Ok(response)
 }
 
-pub async fn set_icon(
+#[derive(Debug, Deserialize)]
+pub struct IconLibraryQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// 在本地图标库中搜索图标
+pub async fn search_icon_library(
+    State(app_state): State<AppState>,
+    Query(query): Query<IconLibraryQuery>,
+) -> Result<Json<Vec<LibraryIcon>>> {
+    let limit = query.limit.unwrap_or(50).min(200);
+    Ok(Json(app_state.icon_library.search(&query.q, limit).await))
+}
+
+/// 重新扫描本地图标库，返回图标数量
+pub async fn reindex_icon_library(State(app_state): State<AppState>) -> Result<Json<usize>> {
+    Ok(Json(app_state.icon_library.reindex().await))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetLibraryIconRequest {
+    /// Path relative to the icon library directory.
+    pub path: String,
+}
+
+/// 将图标库中的图标设置为书签图标，不会复制到 `ICON_DIR`
+pub async fn set_library_icon(
     State(app_state): State<AppState>,
     Path(id): Path<Uuid>,
-    mut multipart: Multipart,
+    Json(payload): Json<SetLibraryIconRequest>,
 ) -> Result<()> {
+    if !app_state.icon_library.contains(&payload.path).await {
+        return Err(Error::BadRequest);
+    }
+
     let mut storage = app_state.storage.lock().await;
-    let Some(profile) = storage.profiles.iter_mut().find_map(|p| {
-        p.bookmarks
-            .iter_mut()
-            .find_map(|b| if b.id == id { Some(b) } else { None })
-    }) else {
+    let Some(bookmark) = storage.get_bookmark_mut(id) else {
         return Err(Error::BookmarkNotFound);
     };
+    remove_icon_file(bookmark).await;
+    bookmark.icon = Some(format!("{LIBRARY_ICON_PREFIX}{}", payload.path));
+    bookmark.icon_source = IconSource::Uploaded;
+    storage.save_profiles().await?;
+    publish_bookmark_changed(&app_state, &storage, id);
 
-    tokio::fs::create_dir_all(ICON_DIR).await?;
-
-    let mut filename = None;
-    let mut file_content = None;
-
-    while let Some(field) = multipart.next_field().await? {
-        let field_name = field.name().unwrap_or("");
-        if field_name == "icon" {
-            if let Some(name) = field.file_name() {
-                filename = Some(name.to_string());
-            }
+    Ok(())
+}
 
-            let data = field.bytes().await?;
-            file_content = Some(data);
-            break;
-        }
+/// 查询自动获取图标的进度
+pub async fn get_icon_status(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Option<IconFetchStatus>>> {
+    if app_state.storage.lock().await.get_bookmark(id).is_none() {
+        return Err(Error::BookmarkNotFound);
     }
+    Ok(Json(app_state.icon_jobs.status(id).await))
+}
 
-    let (Some(filename), Some(file_content)) = (filename, file_content) else {
-        return Err(Error::BadRequest);
-    };
-    let format = if let Some(ext) = filename.split('.').next_back()
-        && let Some(format) = ImageFormat::from_extension(ext)
-    {
-        format
-    } else if let Ok(format) = image::guess_format(&file_content) {
-        format
-    } else {
-        return Err(Error::InvalidImageFormat);
-    };
-
-    let ext = format.extensions_str()[0];
-    let icon_filename = format!("{id}.{ext}");
-    let icon_path = format!("{ICON_DIR}/{icon_filename}");
-
-    if let Some(old_icon_filename) = profile.icon.as_deref()
-        && old_icon_filename != icon_filename
+/// 手动触发自动获取图标
+pub async fn refetch_icon(State(app_state): State<AppState>, Path(id): Path<Uuid>) -> Result<()> {
     {
-        let old_icon_path = format!("{ICON_DIR}/{old_icon_filename}");
-        let _ = tokio::fs::remove_file(old_icon_path).await;
+        let storage = app_state.storage.lock().await;
+        let Some(bookmark) = storage.get_bookmark(id) else {
+            return Err(Error::BookmarkNotFound);
+        };
+        if !accepts_auto_icon(bookmark) {
+            return Err(Error::BadRequest);
+        }
     }
-    profile.icon = Some(icon_filename);
-    tokio::fs::write(icon_path, file_content).await?;
-
+    app_state.icon_jobs.enqueue([id]).await;
     Ok(())
 }
 
@@ -444,75 +1236,139 @@ pub async fn upload_background_image(
     Query(params): Query<UploadBackgroundImageQuery>,
     mut multipart: Multipart,
 ) -> Result<Json<BackgroundImage>> {
-    let mut storage = app_state.storage.lock().await;
-    let Some(profile) = storage.get_profile_mut(&params.profile) else {
-        return Err(Error::ProfileNotFound);
+    let (strip_metadata, upload_config) = {
+        let storage = app_state.storage.lock().await;
+        if storage.get_profile(&params.profile).is_none() {
+            return Err(Error::ProfileNotFound);
+        }
+        (
+            storage.config.background.strip_metadata,
+            storage.config.upload.clone(),
+        )
     };
 
-    tokio::fs::create_dir_all(BACKGROUND_DIR).await?;
+    // 先流式写入临时文件，不在内存中缓存整个文件
+    let upload =
+        upload::receive_file(&mut multipart, "image", upload_config.max_background_bytes).await?;
 
-    let mut filename = None;
-    let mut file_content = None;
+    // 验证图片格式
+    let format = upload.format().ok_or(Error::InvalidImageFormat)?;
 
-    while let Some(field) = multipart.next_field().await? {
-        let field_name = field.name().unwrap_or("");
-        if field_name == "image" {
-            if let Some(name) = field.file_name() {
-                filename = Some(name.to_string());
+    // 相同的图片已经在图库中时直接引用
+    let hash = upload.hash.clone();
+    {
+        let mut storage = app_state.storage.lock().await;
+        if let Some(image) = storage
+            .images
+            .iter()
+            .find(|i| i.hash.as_deref() == Some(hash.as_str()))
+            .cloned()
+        {
+            let Some(profile) = storage.get_profile_mut(&params.profile) else {
+                return Err(Error::ProfileNotFound);
+            };
+            if !profile.background_images.iter().any(|bg| bg.id == image.id) {
+                profile.background_images.push(image.clone());
+                storage.save_profiles().await?;
+                publish_background_changed(&app_state, &params.profile, image.id);
             }
-
-            let data = field.bytes().await?;
-            file_content = Some(data);
-            break;
+            return Ok(Json(image));
         }
     }
 
-    let (Some(filename), Some(file_content)) = (filename, file_content) else {
-        return Err(Error::BadRequest);
-    };
-
-    // 验证图片格式
-    let format = if let Some(ext) = filename.split('.').next_back()
-        && let Some(format) = ImageFormat::from_extension(ext)
-    {
-        format
-    } else if let Ok(format) = image::guess_format(&file_content) {
-        format
-    } else {
-        return Err(Error::InvalidImageFormat);
-    };
-
-    // 检测图片尺寸以自动判断横竖图
-    let img = image::load_from_memory(&file_content).map_err(|_| Error::InvalidImageFormat)?;
-    let (width, height) = img.dimensions();
-    let orientation = if width > height {
-        ImageOrientation::Landscape
-    } else {
-        ImageOrientation::Portrait
-    };
+    upload::check_quota(&upload_config, upload.size).await?;
+    tokio::fs::create_dir_all(BACKGROUND_DIR).await?;
 
-    // 生成唯一 ID 和文件名
+    // 生成唯一 ID
     let id = Uuid::new_v4();
-    let ext = format.extensions_str()[0];
-    let bg_filename = format!("{id}.{ext}");
-    let bg_path = format!("{BACKGROUND_DIR}/{bg_filename}");
 
-    // 保存文件
-    tokio::fs::write(&bg_path, file_content).await?;
+    // 解码和生成缩放图片比较耗时，不持有锁
+    let (bg_filename, processed) = tokio::task::spawn_blocking(move || {
+        // 按 EXIF 旋转后再判断横竖图，解码失败时不会留下任何文件
+        let reader = std::io::BufReader::new(std::fs::File::open(upload.path())?);
+        let img = background::decode_oriented(reader).map_err(|_| Error::InvalidImageFormat)?;
+        // 保存文件
+        let bg_filename = background::store_original(id, upload, format, &img, strip_metadata)?;
+        match background::process_background(id, &img) {
+            Ok(processed) => Ok::<_, Error>((bg_filename, processed)),
+            Err(err) => {
+                let _ = std::fs::remove_file(format!("{BACKGROUND_DIR}/{bg_filename}"));
+                Err(err.into())
+            }
+        }
+    })
+    .await
+    .map_err(anyhow::Error::from)??;
 
     // 创建背景图记录
-    let background_image = BackgroundImage {
+    let mut background_image = BackgroundImage {
         id,
         filename: bg_filename,
-        orientation,
+        orientation: processed.orientation.clone(),
+        width: None,
+        height: None,
+        variants: vec![],
+        blurhash: None,
+        dominant_color: None,
+        text_color: None,
+        hash: Some(hash),
     };
+    processed.apply(&mut background_image);
 
-    // 添加到 profile
+    // 添加到图库和 profile，并立即保存
+    let mut storage = app_state.storage.lock().await;
+    let Some(profile) = storage.get_profile_mut(&params.profile) else {
+        remove_background_files(&background_image).await;
+        return Err(Error::ProfileNotFound);
+    };
     profile.background_images.push(background_image.clone());
+    storage.images.push(background_image.clone());
+
+    let saved = match storage.save_images().await {
+        Ok(()) => storage.save_profiles().await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = saved {
+        // 保存失败时回滚，避免记录和文件不一致
+        storage.images.retain(|bg| bg.id != id);
+        if let Some(profile) = storage.get_profile_mut(&params.profile) {
+            profile.background_images.retain(|bg| bg.id != id);
+        }
+        remove_background_files(&background_image).await;
+        return Err(err.into());
+    }
+    publish_background_changed(&app_state, &params.profile, id);
 
     Ok(Json(background_image))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AttachBackgroundImageQuery {
+    pub profile: String,
+    pub id: Uuid,
+}
+
+/// 将图库中的背景图添加到 profile
+pub async fn attach_background_image(
+    State(app_state): State<AppState>,
+    Query(params): Query<AttachBackgroundImageQuery>,
+) -> Result<Json<BackgroundImage>> {
+    let mut storage = app_state.storage.lock().await;
+    let Some(image) = storage.images.iter().find(|i| i.id == params.id).cloned() else {
+        return Err(Error::BadRequest);
+    };
+    let Some(profile) = storage.get_profile_mut(&params.profile) else {
+        return Err(Error::ProfileNotFound);
+    };
+    if !profile.background_images.iter().any(|bg| bg.id == image.id) {
+        profile.background_images.push(image.clone());
+    }
+    storage.save_profiles().await?;
+    publish_background_changed(&app_state, &params.profile, image.id);
+
+    Ok(Json(image))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DeleteBackgroundImageQuery {
     pub profile: String,
@@ -535,18 +1391,164 @@ pub async fn delete_background_image(
         .iter()
         .position(|bg| bg.id == params.id)
     {
-        let bg = profile.background_images.remove(index);
-
-        // 删除文件
-        let bg_path = format!("{}/{}", BACKGROUND_DIR, bg.filename);
-        let _ = tokio::fs::remove_file(bg_path).await;
+        profile.background_images.remove(index);
     } else {
         return Err(Error::BadRequest);
     }
 
+    // 没有其他 profile 引用时移到回收站，彻底删除时才删除文件
+    storage.trash_unused_images(&params.profile);
+    storage.save_profiles().await?;
+    storage.save_images().await?;
+    storage.save_trash().await?;
+    publish_background_changed(&app_state, &params.profile, params.id);
+
+    Ok(())
+}
+
+/// 获取回收站中的条目，最近删除的在前
+pub async fn get_trash(State(app_state): State<AppState>) -> Result<Json<Vec<TrashEntry>>> {
+    let storage = app_state.storage.lock().await;
+    Ok(Json(storage.trash.iter().rev().cloned().collect()))
+}
+
+/// 从回收站恢复到原来的位置
+pub async fn restore_trash_entry(
+    State(app_state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<()> {
+    let mut storage = app_state.storage.lock().await;
+    let Some(position) = storage.trash.iter().position(|entry| entry.id == id) else {
+        return Err(Error::BadRequest);
+    };
+    let auto_fetch = storage.config.icon.auto_fetch;
+
+    let mut fetch_icons = vec![];
+    match storage.trash[position].item.clone() {
+        TrashItem::Bookmark {
+            profile: name,
+            mut bookmark,
+            index,
+        } => {
+            let Some(before) = storage.get_profile(&name).cloned() else {
+                return Err(Error::ProfileNotFound);
+            };
+            if before.bookmarks.iter().any(|b| b.id == bookmark.id) {
+                return Err(Error::BadRequest);
+            }
+            let mut after = before.clone();
+            after.bookmarks.push(bookmark.clone());
+            keyword::check_keywords(&storage.profiles, &after)?;
+            if let Some(file) = bookmark.icon.as_deref().and_then(icon_file)
+                && !gc::recover_file(ICON_DIR, "icons", file).await
+            {
+                bookmark.icon = None;
+            }
+            let profile = storage.get_profile_mut(&name).unwrap();
+            for tag in &bookmark.tags {
+                if !profile.tags.contains(tag) {
+                    profile.tags.push(tag.clone());
+                }
+            }
+            let index = index.min(profile.bookmarks.len());
+            profile.bookmarks.insert(index, bookmark);
+            if auto_fetch {
+                fetch_icons = icons_to_fetch(&before.bookmarks, &profile.bookmarks);
+            }
+            storage.trash.remove(position);
+            storage.record_history(actor, HistoryAction::Restore, Some(&before), Some(&name));
+            app_state
+                .events
+                .publish(EventKind::Bookmark, Some(&name), json!({}));
+        }
+        TrashItem::Profile { profile, index } => {
+            if storage.get_profile(&profile.name).is_some() {
+                return Err(Error::ProfileAlreadyExists);
+            }
+            keyword::check_keywords(&storage.profiles, &profile)?;
+            // 背景图可能已经从图库中移除
+            for bg in &profile.background_images {
+                if !storage.images.iter().any(|i| i.id == bg.id) {
+                    storage.images.push(bg.clone());
+                }
+                storage.trash.retain(|entry| {
+                    !matches!(&entry.item, TrashItem::BackgroundImage { image, .. } if image.id == bg.id)
+                });
+            }
+            if auto_fetch {
+                fetch_icons = icons_to_fetch(&[], &profile.bookmarks);
+            }
+            let name = profile.name.clone();
+            let index = index.min(storage.profiles.len());
+            storage.profiles.insert(index, profile);
+            storage.sync_background_images();
+            storage.trash.retain(|entry| entry.id != id);
+            storage.record_history(actor, HistoryAction::Restore, None, Some(&name));
+            app_state
+                .events
+                .publish(EventKind::Profile, None, json!({ "created": name }));
+        }
+        TrashItem::BackgroundImage { image, profiles } => {
+            if !storage.images.iter().any(|i| i.id == image.id) {
+                storage.images.push(image.clone());
+            }
+            for name in &profiles {
+                if let Some(profile) = storage.get_profile_mut(name)
+                    && !profile.background_images.iter().any(|bg| bg.id == image.id)
+                {
+                    profile.background_images.push(image.clone());
+                    publish_background_changed(&app_state, name, image.id);
+                }
+            }
+            storage.trash.remove(position);
+        }
+    }
+    storage.save_profiles().await?;
+    storage.save_images().await?;
+    storage.save_trash().await?;
+    drop(storage);
+
+    app_state.icon_jobs.enqueue(fetch_icons).await;
+    Ok(())
+}
+
+/// 彻底删除回收站中的条目
+pub async fn purge_trash_entry(
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<()> {
+    let mut storage = app_state.storage.lock().await;
+    if trash::purge(&mut storage, &[id]).await == 0 {
+        return Err(Error::BadRequest);
+    }
+    storage.save_trash().await?;
     Ok(())
 }
 
+/// 清空回收站，返回删除的条目数量
+pub async fn empty_trash(State(app_state): State<AppState>) -> Result<Json<usize>> {
+    let mut storage = app_state.storage.lock().await;
+    let ids: Vec<Uuid> = storage.trash.iter().map(|entry| entry.id).collect();
+    let purged = trash::purge(&mut storage, &ids).await;
+    storage.save_trash().await?;
+    Ok(Json(purged))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GcQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 清理未引用的图标和背景图，返回回收的空间
+pub async fn collect_garbage(
+    State(app_state): State<AppState>,
+    Query(query): Query<GcQuery>,
+) -> Result<Json<GcReport>> {
+    Ok(Json(gc::collect_garbage(&app_state, query.dry_run).await?))
+}
+
 #[derive(Debug, Serialize)]
 pub struct BackgroundImageListResponse {
     pub images: Vec<BackgroundImageInfo>,
@@ -558,6 +1560,120 @@ pub struct BackgroundImageInfo {
     pub filename: String,
     pub orientation: ImageOrientation,
     pub url: String, // 相对 URL，前端可以通过 /images/backgrounds/{filename} 访问
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// One entry per format, usable as `<source type srcset>`.
+    pub sources: Vec<BackgroundImageSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blurhash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dominant_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_color: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackgroundImageSource {
+    #[serde(rename = "type")]
+    pub mime: &'static str,
+    /// e.g. `/images/backgrounds/{id}-640.avif 640w, /images/backgrounds/{id}-1280.avif 1280w`
+    pub srcset: String,
+}
+
+impl From<&BackgroundImage> for BackgroundImageInfo {
+    fn from(bg: &BackgroundImage) -> Self {
+        let sources = [VariantFormat::Avif, VariantFormat::Jpeg]
+            .into_iter()
+            .filter_map(|format| {
+                let srcset = bg
+                    .variants
+                    .iter()
+                    .filter(|v| v.format == format)
+                    .map(|v| format!("/images/backgrounds/{} {}w", v.filename, v.width))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (!srcset.is_empty()).then_some(BackgroundImageSource {
+                    mime: format.mime(),
+                    srcset,
+                })
+            })
+            .collect();
+
+        Self {
+            id: bg.id,
+            filename: bg.filename.clone(),
+            orientation: bg.orientation.clone(),
+            url: format!("/images/backgrounds/{}", bg.filename),
+            width: bg.width,
+            height: bg.height,
+            sources,
+            blurhash: bg.blurhash.clone(),
+            dominant_color: bg.dominant_color.clone(),
+            text_color: bg.text_color.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryImageInfo {
+    #[serde(flatten)]
+    pub image: BackgroundImageInfo,
+    /// Profiles using this image.
+    pub profiles: Vec<String>,
+}
+
+/// 获取所有 profile 共用的背景图库
+pub async fn get_background_library(
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<LibraryImageInfo>>> {
+    let storage = app_state.storage.lock().await;
+    let images = storage
+        .images
+        .iter()
+        .map(|image| LibraryImageInfo {
+            image: BackgroundImageInfo::from(image),
+            profiles: storage
+                .profiles
+                .iter()
+                .filter(|p| p.background_images.iter().any(|bg| bg.id == image.id))
+                .map(|p| p.name.clone())
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(images))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CurrentBackgroundImageQuery {
+    pub profile: String,
+    pub orientation: Option<ImageOrientation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CurrentBackgroundImageResponse {
+    pub image: Option<BackgroundImageInfo>,
+    /// When the clients should ask again.
+    pub next_change_at: DateTime<Utc>,
+}
+
+/// 按轮换规则获取当前的背景图
+pub async fn get_current_background_image(
+    State(app_state): State<AppState>,
+    Query(params): Query<CurrentBackgroundImageQuery>,
+) -> Result<Json<CurrentBackgroundImageResponse>> {
+    let storage = app_state.storage.lock().await;
+    let Some(profile) = storage.get_profile(&params.profile) else {
+        return Err(Error::ProfileNotFound);
+    };
+
+    let current = rotation::current_background(profile, params.orientation.as_ref(), Utc::now());
+    Ok(Json(CurrentBackgroundImageResponse {
+        image: current.image.map(BackgroundImageInfo::from),
+        next_change_at: current.next_change_at,
+    }))
 }
 
 /// 获取背景图列表
@@ -573,13 +1689,360 @@ pub async fn get_background_images(
     let images: Vec<BackgroundImageInfo> = profile
         .background_images
         .iter()
-        .map(|bg| BackgroundImageInfo {
-            id: bg.id,
-            filename: bg.filename.clone(),
-            orientation: bg.orientation.clone(),
-            url: format!("/images/backgrounds/{}", bg.filename),
-        })
+        .map(BackgroundImageInfo::from)
         .collect();
 
     Ok(Json(BackgroundImageListResponse { images }))
 }
+
+#[derive(Debug, Serialize)]
+pub struct TargetStatus {
+    #[serde(flatten)]
+    pub target: TargetHealth,
+    pub state: HealthState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert_days_until_expiry: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookmarkStatus {
+    pub id: Uuid,
+    /// Up if any link is up.
+    pub state: HealthState,
+    /// A certificate of any link expires within `cert_warning_days` or has expired.
+    pub cert_expiring: bool,
+    pub targets: Vec<TargetStatus>,
+}
+
+/// 获取 profile 中书签的健康检查结果
+pub async fn get_status(
+    State(app_state): State<AppState>,
+    Query(params): Query<OptionalProfileQuery>,
+) -> Result<Json<Vec<BookmarkStatus>>> {
+    let (ids, cert_warning_days): (Vec<Uuid>, i64) = {
+        let mut storage = app_state.storage.lock().await;
+        let cert_warning_days = storage.config.health.cert_warning_days;
+        let profile = if let Some(name) = params.profile.as_deref()
+            && let Some(profile) = storage.get_profile(name)
+        {
+            profile
+        } else {
+            storage.get_default_profile().await
+        };
+        (
+            profile.bookmarks.iter().map(|b| b.id).collect(),
+            cert_warning_days,
+        )
+    };
+
+    let mut statuses = vec![];
+    for id in ids {
+        let Some(targets) = app_state.health.get(id).await else {
+            continue;
+        };
+        let targets: Vec<TargetStatus> = targets
+            .into_iter()
+            .map(|target| TargetStatus {
+                state: target.state(),
+                uptime: target.uptime(),
+                cert_days_until_expiry: target
+                    .certificate
+                    .as_ref()
+                    .map(|cert| cert.days_until_expiry()),
+                target,
+            })
+            .collect();
+        let cert_expiring = targets
+            .iter()
+            .filter_map(|t| t.cert_days_until_expiry)
+            .any(|days| days <= cert_warning_days);
+        let state = if targets.iter().any(|t| t.state == HealthState::Up) {
+            HealthState::Up
+        } else if targets.iter().any(|t| t.state == HealthState::Down) {
+            HealthState::Down
+        } else {
+            HealthState::Unknown
+        };
+        statuses.push(BookmarkStatus {
+            id,
+            state,
+            cert_expiring,
+            targets,
+        });
+    }
+
+    Ok(Json(statuses))
+}
+
+/// 获取最近一次链接检查的结果
+pub async fn get_link_report(
+    State(app_state): State<AppState>,
+    Query(params): Query<ProfileQuery>,
+) -> Result<Json<Option<LinkReport>>> {
+    Ok(Json(app_state.link_audits.report(&params.profile).await))
+}
+
+/// 在后台检查 profile 中所有书签的链接
+pub async fn start_link_audit(
+    State(app_state): State<AppState>,
+    Query(params): Query<ProfileQuery>,
+) -> Result<Json<LinkReport>> {
+    links::start_audit(&app_state, &params.profile)
+        .await
+        .map(Json)
+        .ok_or(Error::ProfileNotFound)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinkActionRequest {
+    pub action: LinkAction,
+    pub ids: Vec<Uuid>,
+    /// Tag added by `tag_broken`.
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkActionResponse {
+    pub updated: usize,
+}
+
+/// 批量处理链接检查结果中的书签
+pub async fn apply_link_action(
+    State(app_state): State<AppState>,
+    actor: Actor,
+    session: UndoSession,
+    Query(params): Query<ProfileQuery>,
+    Json(payload): Json<LinkActionRequest>,
+) -> Result<Json<LinkActionResponse>> {
+    let report = app_state.link_audits.report(&params.profile).await;
+    let mut storage = app_state.storage.lock().await;
+    let auto_fetch = storage.config.icon.auto_fetch;
+    let Some(profile) = storage.get_profile_mut(&params.profile) else {
+        return Err(Error::ProfileNotFound);
+    };
+
+    let before = profile.clone();
+    let selected = |id: &Uuid| payload.ids.contains(id);
+    let mut updated = 0;
+    match payload.action {
+        LinkAction::UpdateRedirect => {
+            let Some(report) = report else {
+                return Err(Error::BadRequest);
+            };
+            for bookmark in profile.bookmarks.iter_mut().filter(|b| selected(&b.id)) {
+                // 只处理检查后没有修改过的链接，临时重定向不能替换原链接
+                if let Some(result) = report.result(bookmark.id)
+                    && result.url == bookmark.url
+                    && let LinkStatus::Redirect {
+                        target,
+                        permanent: true,
+                        ..
+                    } = &result.status
+                {
+                    bookmark.url = target.clone();
+                    updated += 1;
+                }
+            }
+        }
+        LinkAction::TagBroken => {
+            let tag = payload.tag.unwrap_or_else(|| "broken".to_string());
+            for bookmark in profile.bookmarks.iter_mut().filter(|b| selected(&b.id)) {
+                if !bookmark.tags.contains(&tag) {
+                    bookmark.tags.push(tag.clone());
+                    updated += 1;
+                }
+            }
+            if updated > 0 && !profile.tags.contains(&tag) {
+                profile.tags.push(tag);
+            }
+        }
+        LinkAction::Delete => {
+            let before = profile.bookmarks.len();
+            profile.bookmarks.retain(|b| !selected(&b.id));
+            updated = before - profile.bookmarks.len();
+        }
+    }
+
+    let fetch_icons = if auto_fetch {
+        icons_to_fetch(&before.bookmarks, &profile.bookmarks)
+    } else {
+        vec![]
+    };
+    storage.trash_removed_bookmarks(&before);
+    storage.save_profiles().await?;
+    storage.save_trash().await?;
+    storage.record_history(
+        actor,
+        HistoryAction::LinkAction,
+        Some(&before),
+        Some(&params.profile),
+    );
+    if updated > 0
+        && let Some(after) = storage.get_profile(&params.profile).cloned()
+    {
+        push_undo(
+            &app_state,
+            &storage,
+            session,
+            Operation::Update {
+                before: Box::new(before),
+                after: Box::new(after),
+            },
+        )
+        .await;
+    }
+    drop(storage);
+
+    if updated > 0 {
+        app_state.events.publish(
+            EventKind::Bookmark,
+            Some(&params.profile),
+            json!({ "ids": payload.ids }),
+        );
+    }
+    app_state.icon_jobs.enqueue(fetch_icons).await;
+    Ok(Json(LinkActionResponse { updated }))
+}
+
+fn publish_bookmark_changed(app_state: &AppState, storage: &Storage, id: Uuid) {
+    app_state.events.publish(
+        EventKind::Bookmark,
+        storage.bookmark_profile(id),
+        json!({ "ids": [id] }),
+    );
+}
+
+fn publish_background_changed(app_state: &AppState, profile: &str, id: Uuid) {
+    app_state
+        .events
+        .publish(EventKind::Background, Some(profile), json!({ "id": id }));
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Only events of this profile and of all profiles, every event if missing.
+    pub profile: Option<String>,
+    /// For clients that cannot set the `Last-Event-ID` header.
+    pub last_event_id: Option<u64>,
+}
+
+/// 订阅数据变更事件，断线重连时通过 `Last-Event-ID` 补发错过的事件
+pub async fn events(
+    State(app_state): State<AppState>,
+    Query(params): Query<EventsQuery>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = std::result::Result<Event, std::convert::Infallible>>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .or(params.last_event_id);
+    Sse::new(app_state.events.subscribe(params.profile, last_event_id))
+        .keep_alive(KeepAlive::default())
+}
+
+async fn push_undo(
+    app_state: &AppState,
+    storage: &Storage,
+    session: UndoSession,
+    operation: Operation,
+) {
+    let depth = storage.config.history.undo_depth;
+    app_state.undo.push(session, operation, depth).await;
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoResponse {
+    pub operation: OperationKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(flatten)]
+    pub state: UndoState,
+}
+
+/// 查询当前会话可以撤销和重做的操作数量
+pub async fn get_undo_state(
+    State(app_state): State<AppState>,
+    session: UndoSession,
+) -> Result<Json<UndoState>> {
+    Ok(Json(app_state.undo.state(&session).await))
+}
+
+/// 撤销当前会话最近的操作
+pub async fn undo(
+    State(app_state): State<AppState>,
+    actor: Actor,
+    session: UndoSession,
+) -> Result<Json<UndoResponse>> {
+    undo_step(&app_state, actor, &session, false)
+        .await
+        .map(Json)
+}
+
+/// 重做当前会话最近撤销的操作
+pub async fn redo(
+    State(app_state): State<AppState>,
+    actor: Actor,
+    session: UndoSession,
+) -> Result<Json<UndoResponse>> {
+    undo_step(&app_state, actor, &session, true).await.map(Json)
+}
+
+async fn undo_step(
+    app_state: &AppState,
+    actor: Actor,
+    session: &UndoSession,
+    forward: bool,
+) -> Result<UndoResponse> {
+    let mut storage = app_state.storage.lock().await;
+    let profiles_before = storage.profiles.clone();
+    let (operation, state) = app_state.undo.step(session, &mut storage, forward).await?;
+
+    let profile = operation.profile().map(str::to_string);
+    let kind = operation.kind();
+    let mut fetch_icons = vec![];
+    if let Some(name) = &profile
+        && matches!(
+            kind,
+            OperationKind::Create | OperationKind::Update | OperationKind::Delete
+        )
+    {
+        let before = profiles_before.iter().find(|p| p.name == *name);
+        let action = if forward {
+            HistoryAction::Redo
+        } else {
+            HistoryAction::Undo
+        };
+        storage.record_history(actor, action, before, Some(name));
+        if storage.config.icon.auto_fetch
+            && let Some(after) = storage.get_profile(name)
+        {
+            fetch_icons = icons_to_fetch(
+                before.map(|p| p.bookmarks.as_slice()).unwrap_or_default(),
+                &after.bookmarks,
+            );
+        }
+    }
+    drop(storage);
+
+    match (kind, &profile) {
+        (OperationKind::Update, Some(name)) => {
+            app_state
+                .events
+                .publish(EventKind::Bookmark, Some(name), json!({}))
+        }
+        _ => app_state
+            .events
+            .publish(EventKind::Profile, None, json!({ "undo": !forward })),
+    }
+    app_state.icon_jobs.enqueue(fetch_icons).await;
+
+    Ok(UndoResponse {
+        operation: kind,
+        profile,
+        state,
+    })
+}
@@ -0,0 +1,111 @@
+use serde::Deserialize;
+
+use crate::xml;
+
+/// `Bookmark.icon` value for a generated letter avatar.
+///
+/// Options can be appended as a query string, e.g. `generated?shape=circle&font=serif`.
+pub const GENERATED_ICON: &str = "generated";
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvatarShape {
+    Circle,
+    #[default]
+    Rounded,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvatarFont {
+    #[default]
+    Sans,
+    Serif,
+    Mono,
+}
+
+impl AvatarFont {
+    fn family(self) -> &'static str {
+        match self {
+            AvatarFont::Sans => "system-ui, -apple-system, 'Segoe UI', Roboto, sans-serif",
+            AvatarFont::Serif => "Georgia, 'Times New Roman', serif",
+            AvatarFont::Mono => "ui-monospace, Menlo, Consolas, monospace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct AvatarOptions {
+    pub shape: AvatarShape,
+    pub font: AvatarFont,
+}
+
+impl AvatarOptions {
+    /// Parse the options stored in a generated `Bookmark.icon` value.
+    pub fn from_icon(icon: &str) -> Option<Self> {
+        let query = icon.strip_prefix(GENERATED_ICON)?;
+        let query = match query.strip_prefix('?') {
+            Some(query) => query,
+            None if query.is_empty() => "",
+            None => return None,
+        };
+
+        let mut options = Self::default();
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            let value = serde::de::value::StrDeserializer::<serde::de::value::Error>::new(&value);
+            match key.as_ref() {
+                "shape" => options.shape = AvatarShape::deserialize(value).unwrap_or_default(),
+                "font" => options.font = AvatarFont::deserialize(value).unwrap_or_default(),
+                _ => {}
+            }
+        }
+        Some(options)
+    }
+}
+
+/// Whether the `Bookmark.icon` value refers to a generated avatar.
+pub fn is_generated(icon: &str) -> bool {
+    AvatarOptions::from_icon(icon).is_some()
+}
+
+/// 根据标题和 URL 生成字母头像
+pub fn render_svg(title: &str, url: &str, options: AvatarOptions) -> String {
+    let letter = title
+        .trim()
+        .chars()
+        .next()
+        .map(|c| c.to_uppercase().to_string())
+        .unwrap_or_else(|| "?".to_string());
+    let host = url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string());
+    let hue = fnv1a(host.as_bytes()) % 360;
+
+    let background = match options.shape {
+        AvatarShape::Circle => {
+            format!(r#"<circle cx="64" cy="64" r="64" fill="hsl({hue}, 55%, 45%)"/>"#)
+        }
+        AvatarShape::Rounded => {
+            format!(r#"<rect width="128" height="128" rx="28" fill="hsl({hue}, 55%, 45%)"/>"#)
+        }
+        AvatarShape::Square => {
+            format!(r#"<rect width="128" height="128" fill="hsl({hue}, 55%, 45%)"/>"#)
+        }
+    };
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="128" height="128" viewBox="0 0 128 128">{background}<text x="64" y="64" dy="0.35em" text-anchor="middle" font-family="{}" font-size="64" font-weight="600" fill="white">{}</text></svg>"#,
+        options.font.family(),
+        xml::escape(&letter),
+    )
+}
+
+/// Stable hash so the same host always gets the same color.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash: u32, b| {
+        (hash ^ *b as u32).wrapping_mul(0x01000193)
+    })
+}
@@ -0,0 +1,318 @@
+use std::io::{BufRead, Cursor, Seek};
+
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageDecoder, ImageFormat, ImageReader, ImageResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::api::BACKGROUND_DIR;
+use crate::bookmark::{BackgroundImage, ImageOrientation};
+use crate::events::EventKind;
+use crate::state::AppState;
+use crate::upload::TempUpload;
+
+/// Widths of the resized variants, only those smaller than the original are generated.
+const VARIANT_WIDTHS: [u32; 4] = [640, 1280, 1920, 2560];
+
+/// Images whose aspect ratio is within 10% of 1:1 fit both layouts.
+const SQUARE_TOLERANCE: f64 = 0.1;
+
+/// Formats of the resized variants: AVIF for current browsers, JPEG as the fallback.
+///
+/// The fallback is JPEG rather than WebP because the `image` crate only encodes lossless WebP,
+/// which is often larger than the original photo, and a lossy encoder would need libwebp. JPEG
+/// also works in every browser, including those without WebP support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VariantFormat {
+    Avif,
+    Jpeg,
+}
+
+impl VariantFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            VariantFormat::Avif => "avif",
+            VariantFormat::Jpeg => "jpg",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            VariantFormat::Avif => "image/avif",
+            VariantFormat::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+/// A resized copy of a background image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub filename: String,
+    pub format: VariantFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Everything derived from the original image.
+#[derive(Debug, Clone)]
+pub struct ProcessedBackground {
+    pub orientation: ImageOrientation,
+    pub width: u32,
+    pub height: u32,
+    pub variants: Vec<ImageVariant>,
+    pub blurhash: Option<String>,
+    pub dominant_color: String,
+    pub text_color: String,
+}
+
+impl ProcessedBackground {
+    pub fn apply(self, bg: &mut BackgroundImage) {
+        bg.orientation = self.orientation;
+        bg.width = Some(self.width);
+        bg.height = Some(self.height);
+        bg.variants = self.variants;
+        bg.blurhash = self.blurhash;
+        bg.dominant_color = Some(self.dominant_color);
+        bg.text_color = Some(self.text_color);
+    }
+}
+
+/// 根据宽高判断横图、竖图或方图
+pub fn classify(width: u32, height: u32) -> ImageOrientation {
+    let ratio = width as f64 / height.max(1) as f64;
+    if (ratio - 1.0).abs() <= SQUARE_TOLERANCE {
+        ImageOrientation::Square
+    } else if width > height {
+        ImageOrientation::Landscape
+    } else {
+        ImageOrientation::Portrait
+    }
+}
+
+/// 解码图片并应用 EXIF 中的旋转信息
+pub fn decode_oriented(reader: impl BufRead + Seek) -> ImageResult<DynamicImage> {
+    let mut decoder = ImageReader::new(reader)
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// 保存原图，返回文件名
+///
+/// With `strip_metadata` the image is re-encoded from the already rotated pixels, which drops
+/// EXIF data such as GPS coordinates. Formats without an encoder are stored as PNG.
+pub fn store_original(
+    id: Uuid,
+    upload: TempUpload,
+    format: ImageFormat,
+    img: &DynamicImage,
+    strip_metadata: bool,
+) -> anyhow::Result<String> {
+    if !strip_metadata {
+        let filename = format!("{id}.{}", format.extensions_str()[0]);
+        upload.persist(format!("{BACKGROUND_DIR}/{filename}"))?;
+        return Ok(filename);
+    }
+
+    let (content, ext) = if format == ImageFormat::Jpeg {
+        let mut buffer = Cursor::new(Vec::new());
+        img.to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, 92))?;
+        (buffer.into_inner(), format.extensions_str()[0])
+    } else {
+        let mut buffer = Cursor::new(Vec::new());
+        match img.write_to(&mut buffer, format) {
+            Ok(()) => (buffer.into_inner(), format.extensions_str()[0]),
+            Err(_) => {
+                let mut buffer = Cursor::new(Vec::new());
+                img.write_to(&mut buffer, ImageFormat::Png)?;
+                (buffer.into_inner(), "png")
+            }
+        }
+    };
+
+    let filename = format!("{id}.{ext}");
+    std::fs::write(format!("{BACKGROUND_DIR}/{filename}"), content)?;
+    Ok(filename)
+}
+
+/// 删除背景图的原图和所有缩放后的图片
+pub async fn remove_background_files(bg: &BackgroundImage) {
+    for filename in bg.files() {
+        let _ = tokio::fs::remove_file(format!("{BACKGROUND_DIR}/{filename}")).await;
+    }
+}
+
+/// 生成缩放后的图片、blurhash 和主色调，会阻塞线程
+pub fn process_background(id: Uuid, img: &DynamicImage) -> anyhow::Result<ProcessedBackground> {
+    let (width, height) = img.dimensions();
+
+    let mut widths: Vec<u32> = VARIANT_WIDTHS.into_iter().filter(|w| *w < width).collect();
+    if widths.is_empty() {
+        widths.push(width);
+    }
+
+    let mut variants = Vec::new();
+    for variant_width in widths {
+        let resized = if variant_width == width {
+            img.clone()
+        } else {
+            img.resize(variant_width, u32::MAX, FilterType::Lanczos3)
+        };
+        for format in [VariantFormat::Avif, VariantFormat::Jpeg] {
+            let filename = format!("{id}-{variant_width}.{}", format.extension());
+            let content = encode(&resized, format)?;
+            std::fs::write(format!("{BACKGROUND_DIR}/{filename}"), content)?;
+            variants.push(ImageVariant {
+                filename,
+                format,
+                width: resized.width(),
+                height: resized.height(),
+            });
+        }
+    }
+
+    // 在缩略图上计算，速度更快
+    let thumbnail = img.thumbnail(64, 64).to_rgba8();
+    let blurhash = blurhash::encode(
+        4,
+        3,
+        thumbnail.width(),
+        thumbnail.height(),
+        thumbnail.as_raw(),
+    )
+    .ok();
+    let dominant = dominant_color(thumbnail.pixels().map(|p| [p[0], p[1], p[2]]));
+
+    Ok(ProcessedBackground {
+        orientation: classify(width, height),
+        width,
+        height,
+        variants,
+        blurhash,
+        dominant_color: hex_color(dominant),
+        text_color: hex_color(contrast_color(dominant)),
+    })
+}
+
+fn encode(img: &DynamicImage, format: VariantFormat) -> anyhow::Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+    match format {
+        VariantFormat::Avif => {
+            let encoder = AvifEncoder::new_with_speed_quality(&mut buffer, 8, 70);
+            img.to_rgba8().write_with_encoder(encoder)?;
+        }
+        VariantFormat::Jpeg => {
+            let encoder = JpegEncoder::new_with_quality(&mut buffer, 82);
+            img.to_rgb8().write_with_encoder(encoder)?;
+        }
+    }
+    Ok(buffer.into_inner())
+}
+
+/// The most common color after reducing each channel to 4 bits.
+fn dominant_color(pixels: impl Iterator<Item = [u8; 3]>) -> [u8; 3] {
+    let mut buckets = vec![(0u32, [0u64; 3]); 4096];
+    for [r, g, b] in pixels {
+        let index = ((r as usize >> 4) << 8) | ((g as usize >> 4) << 4) | (b as usize >> 4);
+        let (count, sum) = &mut buckets[index];
+        *count += 1;
+        sum[0] += r as u64;
+        sum[1] += g as u64;
+        sum[2] += b as u64;
+    }
+
+    let (count, sum) = buckets
+        .into_iter()
+        .max_by_key(|(count, _)| *count)
+        .unwrap_or_default();
+    if count == 0 {
+        return [0, 0, 0];
+    }
+    let count = count as u64;
+    [
+        (sum[0] / count) as u8,
+        (sum[1] / count) as u8,
+        (sum[2] / count) as u8,
+    ]
+}
+
+/// Black or white, whichever is more readable on the given color.
+fn contrast_color([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let luminance = 0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b);
+    if luminance > 0.179 {
+        [0, 0, 0]
+    } else {
+        [255, 255, 255]
+    }
+}
+
+fn hex_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// 为旧的背景图补充缩放图片、颜色信息和哈希
+pub async fn backfill_backgrounds(state: AppState) {
+    let pending: Vec<(Uuid, String, bool)> = {
+        let storage = state.storage.lock().await;
+        storage
+            .images
+            .iter()
+            .filter(|bg| bg.variants.is_empty() || bg.hash.is_none())
+            .map(|bg| (bg.id, bg.filename.clone(), bg.variants.is_empty()))
+            .collect()
+    };
+
+    for (id, filename, needs_variants) in pending {
+        let result = tokio::task::spawn_blocking(move || {
+            let bytes = std::fs::read(format!("{BACKGROUND_DIR}/{filename}"))?;
+            let hash = format!("{:x}", Sha256::digest(&bytes));
+            let processed = if needs_variants {
+                Some(process_background(
+                    id,
+                    &decode_oriented(Cursor::new(&bytes))?,
+                )?)
+            } else {
+                None
+            };
+            Ok::<_, anyhow::Error>((hash, processed))
+        })
+        .await;
+        let (hash, processed) = match result.map_err(anyhow::Error::from).and_then(|r| r) {
+            Ok(result) => result,
+            Err(err) => {
+                tracing::warn!("Failed to process background image {id}: {err}");
+                continue;
+            }
+        };
+
+        let mut storage = state.storage.lock().await;
+        if let Some(bg) = storage.images.iter_mut().find(|bg| bg.id == id) {
+            if let Some(processed) = processed {
+                processed.apply(bg);
+            }
+            bg.hash.get_or_insert(hash);
+            storage.sync_background_images();
+            let _ = storage.save_images().await;
+            let _ = storage.save_profiles().await;
+            state
+                .events
+                .publish(EventKind::Background, None, json!({ "id": id }));
+        }
+    }
+}
@@ -1,17 +1,54 @@
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::background::ImageVariant;
+use crate::health::HealthCheck;
+use crate::network::{INTRANET_ZONE, NetworkRules};
+use crate::rewrite::RewriteRule;
+use crate::rotation::BackgroundRotation;
+use crate::search::{self, SearchEngine};
+use crate::usage::BookmarkStats;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackgroundImage {
     pub id: Uuid,
+    /// The original upload.
     pub filename: String,
     pub orientation: ImageOrientation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Resized copies in `BACKGROUND_DIR`, used for `srcset`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<ImageVariant>,
+    /// Placeholder shown while the image is loading.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blurhash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dominant_color: Option<String>,
+    /// Black or white text color readable on top of the image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_color: Option<String>,
+    /// SHA-256 of the uploaded file, used to deduplicate uploads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl BackgroundImage {
+    /// The original file and all variants.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.filename.as_str())
+            .chain(self.variants.iter().map(|v| v.filename.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ImageOrientation {
     Landscape, // 横图
     Portrait,  // 竖图
+    Square,    // 接近 1:1，横竖屏都适用
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,11 +63,60 @@ pub struct Bookmark {
     /// The intranet url is used to open the bookmark in the internal network.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub intranet_url: Option<String>,
+    /// Links for the other network zones of the profile, e.g. `tailscale`.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub zone_urls: IndexMap<String, String>,
+    /// Links derived from `url` by the rewrite rules of the profile, never stored.
+    #[serde(
+        default,
+        skip_deserializing,
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub derived_urls: IndexMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub search_url: Option<String>,
+    /// Short name opened by `/go/{keyword}`, unique across all profiles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
     pub tags: Vec<String>,
+    /// Icon file name in `ICON_DIR`, or `generated` for a letter avatar.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
+    /// How the icon file was obtained.
+    #[serde(default, skip_serializing_if = "IconSource::is_uploaded")]
+    pub icon_source: IconSource,
+    /// Overrides the defaults of the background health checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+    /// How often the bookmark was opened, kept in the usage stats and never stored here.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<BookmarkStats>,
+}
+
+impl Bookmark {
+    /// The link set for a network zone, `intranet` is stored in `intranet_url`.
+    pub fn zone_url(&self, zone: &str) -> Option<&str> {
+        if zone == INTRANET_ZONE {
+            self.intranet_url.as_deref()
+        } else {
+            self.zone_urls.get(zone).map(String::as_str)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IconSource {
+    /// Uploaded by the user, never replaced automatically.
+    #[default]
+    Uploaded,
+    /// Fetched from the website by the server.
+    Fetched,
+}
+
+impl IconSource {
+    pub fn is_uploaded(&self) -> bool {
+        *self == IconSource::Uploaded
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,13 +126,46 @@ pub struct BookmarkProfile {
     pub name: String,
     pub bookmarks: Vec<Bookmark>,
     pub tags: Vec<String>,
-    pub search_engine: String,
+    /// Web search engines, older versions stored a single url in `search_engine`.
+    #[serde(
+        alias = "search_engine",
+        deserialize_with = "search::deserialize_engines"
+    )]
+    pub search_engines: Vec<SearchEngine>,
     /// A url for checking the intranet connection.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub intranet_check_url: Option<String>,
+    /// Rules for detecting the intranet on the server, `intranet_check_url` is the fallback.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_rules: Option<NetworkRules>,
+    /// Rules deriving zone links for bookmarks without their own.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub url_rewrites: Vec<RewriteRule>,
     /// Background images for the profile.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub background_images: Vec<BackgroundImage>,
+    /// Rules for picking the background on the server, random on the client if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_rotation: Option<BackgroundRotation>,
+    /// How the client orders the bookmarks, `bookmarks` keeps the manual order.
+    #[serde(skip_serializing_if = "SortMode::is_manual")]
+    pub sort_mode: SortMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortMode {
+    #[default]
+    Manual,
+    /// Opened often and recently first.
+    Frecency,
+    /// Opened most often first.
+    MostUsed,
+}
+
+impl SortMode {
+    pub fn is_manual(&self) -> bool {
+        *self == SortMode::Manual
+    }
 }
 
 impl Default for BookmarkProfile {
@@ -55,9 +174,13 @@ impl Default for BookmarkProfile {
             name: "Default".to_string(),
             bookmarks: vec![],
             tags: vec![],
-            search_engine: "https://www.google.com/search?q={}".to_string(),
+            search_engines: search::default_engines(),
             intranet_check_url: None,
+            network_rules: None,
+            url_rewrites: vec![],
             background_images: vec![],
+            background_rotation: None,
+            sort_mode: SortMode::Manual,
         }
     }
 }
@@ -7,6 +7,14 @@ use crate::env::{PASSWORD, USERNAME};
 #[derive(Default)]
 pub struct Config {
     pub auth: AuthConfig,
+    pub icon: IconConfig,
+    pub gc: GcConfig,
+    pub background: BackgroundConfig,
+    pub upload: UploadConfig,
+    pub health: HealthConfig,
+    pub history: HistoryConfig,
+    pub trash: TrashConfig,
+    pub search_history: SearchHistoryConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,3 +37,164 @@ impl Default for AuthConfig {
         }
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IconConfig {
+    /// Fetch a favicon for bookmarks created without an icon.
+    pub auto_fetch: bool,
+    /// Refresh automatically fetched icons every N hours, 0 to disable.
+    pub refresh_interval_hours: u64,
+    /// A local icon pack directory, e.g. a checkout of dashboard-icons or simple-icons.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub library_dir: Option<String>,
+}
+
+impl Default for IconConfig {
+    fn default() -> Self {
+        Self {
+            auto_fetch: true,
+            refresh_interval_hours: 0,
+            library_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GcConfig {
+    /// Remove unreferenced asset files every N hours, 0 to disable.
+    pub interval_hours: u64,
+    /// Keep unreferenced files in quarantine for N days, 0 to delete them directly.
+    pub quarantine_days: u64,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            interval_hours: 24,
+            quarantine_days: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackgroundConfig {
+    /// Remove EXIF and other metadata from uploaded background images.
+    pub strip_metadata: bool,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            strip_metadata: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadConfig {
+    /// Maximum size of an uploaded icon in bytes.
+    pub max_icon_bytes: u64,
+    /// Maximum size of an uploaded background image in bytes.
+    pub max_background_bytes: u64,
+    /// Total size allowed for icons and background images in bytes, 0 for unlimited.
+    pub quota_bytes: u64,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            max_icon_bytes: 10 * 1024 * 1024,
+            max_background_bytes: 50 * 1024 * 1024,
+            quota_bytes: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Check every bookmark every N seconds, 0 to disable.
+    pub interval_secs: u64,
+    /// Check the certificates of HTTPS links every N hours, 0 to disable.
+    ///
+    /// Runs on its own so expiring certificates are flagged even when `interval_secs` is 0.
+    pub cert_interval_hours: u64,
+    /// Timeout of a single check unless the bookmark sets its own.
+    pub timeout_secs: u64,
+    /// Number of results kept per link.
+    pub history_len: usize,
+    /// Flag certificates expiring within N days.
+    pub cert_warning_days: i64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 0,
+            cert_interval_hours: 24,
+            timeout_secs: 10,
+            history_len: 20,
+            cert_warning_days: 14,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Revisions kept per profile, 0 for unlimited.
+    pub max_revisions: usize,
+    /// Drop revisions older than N days, 0 to keep them forever.
+    pub max_age_days: u64,
+    /// Operations each session can undo, 0 to disable undo.
+    pub undo_depth: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_revisions: 500,
+            max_age_days: 90,
+            undo_depth: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashConfig {
+    /// Purge deleted items after N days, 0 to keep them until purged manually.
+    pub retention_days: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchHistoryConfig {
+    /// Store searches to suggest them again and rank chosen bookmarks higher, off unless
+    /// opted in.
+    pub enabled: bool,
+    /// Drop searches older than N days, 0 to keep them forever.
+    pub retention_days: u64,
+    /// Searches kept per user or device, 0 for unlimited.
+    pub max_entries: usize,
+}
+
+impl Default for SearchHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: 90,
+            max_entries: 500,
+        }
+    }
+}
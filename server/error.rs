@@ -7,6 +7,8 @@ pub enum Error {
     BookmarkNotFound,
     #[error("Profile not found")]
     ProfileNotFound,
+    #[error("Revision not found")]
+    RevisionNotFound,
     #[error("Invalid credentials")]
     InvalidCredentials,
     #[error("Unauthorized")]
@@ -22,9 +24,26 @@ pub enum Error {
     InvalidProfileOrder,
     #[error("Invalid image format")]
     InvalidImageFormat,
+    #[error("Invalid rewrite rule: {0}")]
+    InvalidRewriteRule(String),
+    #[error("Invalid keyword: {0}")]
+    InvalidKeyword(String),
+    #[error("Keyword already in use: {0}")]
+    KeywordConflict(String),
+    #[error("Invalid search engine: {0}")]
+    InvalidSearchEngine(String),
+
+    #[error("Nothing to undo")]
+    NothingToUndo,
+    #[error("The data has changed since the operation")]
+    UndoConflict,
 
     #[error("Bad request")]
     BadRequest,
+    #[error("File too large")]
+    FileTooLarge,
+    #[error("Storage quota exceeded")]
+    QuotaExceeded,
 
     #[error(transparent)]
     IO(#[from] std::io::Error),
@@ -42,16 +61,26 @@ impl IntoResponse for Error {
         let status_code = match self {
             Error::BookmarkNotFound => StatusCode::NOT_FOUND,
             Error::ProfileNotFound => StatusCode::NOT_FOUND,
+            Error::RevisionNotFound => StatusCode::NOT_FOUND,
 
             Error::InvalidCredentials => StatusCode::UNAUTHORIZED,
             Error::Unauthorized => StatusCode::UNAUTHORIZED,
             Error::AuthRequired => StatusCode::UNAUTHORIZED,
 
             Error::BadRequest => StatusCode::BAD_REQUEST,
+            Error::NothingToUndo => StatusCode::BAD_REQUEST,
+            Error::UndoConflict => StatusCode::CONFLICT,
             Error::CannotDeleteLastProfile => StatusCode::BAD_REQUEST,
             Error::ProfileAlreadyExists => StatusCode::BAD_REQUEST,
             Error::InvalidProfileOrder => StatusCode::BAD_REQUEST,
             Error::InvalidImageFormat => StatusCode::BAD_REQUEST,
+            Error::InvalidRewriteRule(_) => StatusCode::BAD_REQUEST,
+            Error::InvalidKeyword(_) => StatusCode::BAD_REQUEST,
+            Error::KeywordConflict(_) => StatusCode::CONFLICT,
+            Error::InvalidSearchEngine(_) => StatusCode::BAD_REQUEST,
+
+            Error::FileTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::QuotaExceeded => StatusCode::INSUFFICIENT_STORAGE,
 
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
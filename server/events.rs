@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::response::sse;
+use chrono::Utc;
+use futures_util::Stream;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Events kept for clients resuming with `Last-Event-ID`.
+const HISTORY_LEN: usize = 256;
+
+/// What changed, sent as the event name, e.g. `profile-changed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// Profiles were created, deleted, renamed or their settings changed.
+    Profile,
+    Bookmark,
+    Background,
+    /// Health check results changed.
+    Status,
+}
+
+impl EventKind {
+    fn name(self) -> &'static str {
+        match self {
+            EventKind::Profile => "profile-changed",
+            EventKind::Bookmark => "bookmark-changed",
+            EventKind::Background => "background-changed",
+            EventKind::Status => "status-changed",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Event {
+    pub id: u64,
+    pub kind: EventKind,
+    /// `None` for events every client should see, e.g. the profile list changed.
+    pub profile: Option<String>,
+    pub data: Value,
+}
+
+#[derive(Serialize)]
+struct EventData<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<&'a str>,
+    #[serde(flatten)]
+    data: &'a Value,
+}
+
+impl Event {
+    fn visible_to(&self, profile: Option<&str>) -> bool {
+        match (profile, self.profile.as_deref()) {
+            (Some(wanted), Some(profile)) => wanted == profile,
+            _ => true,
+        }
+    }
+
+    fn to_sse(&self) -> sse::Event {
+        let data = EventData {
+            profile: self.profile.as_deref(),
+            data: &self.data,
+        };
+        sse::Event::default()
+            .id(self.id.to_string())
+            .event(self.kind.name())
+            .json_data(data)
+            .unwrap_or_default()
+    }
+}
+
+/// Publishes changes to the clients connected to `/api/events`.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<Event>>,
+    history: Arc<Mutex<VecDeque<Arc<Event>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(HISTORY_LEN);
+        Self {
+            sender,
+            history: Arc::default(),
+            // 以启动时间开始编号，重启后客户端的 Last-Event-ID 不会和新事件混淆
+            next_id: Arc::new(AtomicU64::new(Utc::now().timestamp_millis() as u64 * 1000)),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn publish(&self, kind: EventKind, profile: Option<&str>, data: Value) {
+        let mut history = self.history.lock().unwrap();
+        let event = Arc::new(Event {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            kind,
+            profile: profile.map(str::to_string),
+            data,
+        });
+        history.push_back(event.clone());
+        while history.len() > HISTORY_LEN {
+            history.pop_front();
+        }
+        // 没有客户端连接时发送会失败，可以忽略
+        let _ = self.sender.send(event);
+    }
+
+    /// 订阅事件，提供 `last_event_id` 时先补发错过的事件
+    pub fn subscribe(
+        &self,
+        profile: Option<String>,
+        last_event_id: Option<u64>,
+    ) -> impl Stream<Item = Result<sse::Event, Infallible>> + use<> {
+        // 先订阅再读取历史，两者都有的事件按 id 跳过
+        let receiver = self.sender.subscribe();
+        let mut replay = VecDeque::new();
+        let mut delivered = 0;
+        if let Some(last_event_id) = last_event_id {
+            let history = self.history.lock().unwrap();
+            let next_id = self.next_id.load(Ordering::Relaxed);
+            let oldest = history.front().map_or(next_id, |e| e.id);
+            if last_event_id < next_id && last_event_id + 1 >= oldest {
+                replay.extend(
+                    history
+                        .iter()
+                        .filter(|e| e.id > last_event_id && e.visible_to(profile.as_deref()))
+                        .map(|e| e.to_sse()),
+                );
+            } else {
+                // 错过的事件已经不在历史中，让客户端重新加载
+                replay.push_back(resync_event());
+            }
+            delivered = next_id.saturating_sub(1);
+        }
+
+        futures_util::stream::unfold(
+            (receiver, replay, delivered, profile),
+            |(mut receiver, mut replay, delivered, profile)| async move {
+                loop {
+                    if let Some(event) = replay.pop_front() {
+                        return Some((Ok(event), (receiver, replay, delivered, profile)));
+                    }
+                    let event = match receiver.recv().await {
+                        Ok(event) => event,
+                        Err(RecvError::Lagged(_)) => {
+                            replay.push_back(resync_event());
+                            continue;
+                        }
+                        Err(RecvError::Closed) => return None,
+                    };
+                    if event.id > delivered && event.visible_to(profile.as_deref()) {
+                        let event = event.to_sse();
+                        return Some((Ok(event), (receiver, replay, delivered, profile)));
+                    }
+                }
+            },
+        )
+    }
+}
+
+/// Tells the client to reload everything, it has no id so `Last-Event-ID` stays unchanged.
+fn resync_event() -> sse::Event {
+    sse::Event::default()
+        .event(EventKind::Profile.name())
+        .data(r#"{"resync":true}"#)
+}
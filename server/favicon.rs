@@ -0,0 +1,242 @@
+use axum::body::Bytes;
+use scraper::{Html, Selector};
+
+use crate::error::{Error, Result};
+
+/// Icons larger than this are skipped.
+const MAX_ICON_BYTES: usize = 5 * 1024 * 1024;
+/// Only the start of a page is needed for its `<link>` tags.
+const MAX_PAGE_BYTES: usize = 2 * 1024 * 1024;
+
+/// An icon downloaded from a website.
+#[derive(Debug, Clone)]
+pub struct FetchedIcon {
+    pub url: String,
+    pub bytes: Bytes,
+    pub content_type: String,
+}
+
+impl FetchedIcon {
+    pub fn is_svg(&self) -> bool {
+        self.content_type.contains("svg") || is_svg(&self.bytes)
+    }
+
+    /// File extension used when storing the icon.
+    pub fn extension(&self) -> Option<&'static str> {
+        if self.is_svg() {
+            return Some("svg");
+        }
+        image::guess_format(&self.bytes)
+            .ok()
+            .map(|format| format.extensions_str()[0])
+    }
+
+    /// Rank used to pick the best icon: SVG first, then the largest bitmap.
+    fn score(&self) -> u64 {
+        if self.is_svg() {
+            return u64::MAX;
+        }
+        match image::load_from_memory(&self.bytes) {
+            Ok(img) => img.width() as u64 * img.height() as u64,
+            Err(_) => 0,
+        }
+    }
+}
+
+/// Settings shared by all requests to bookmarked websites.
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+}
+
+/// HTTP client used for requests to bookmarked websites.
+pub fn http_client() -> Result<reqwest::Client> {
+    http_client_builder().build().map_err(|_| Error::BadRequest)
+}
+
+/// 读取响应内容，超过 `limit` 字节时放弃。没有 Content-Length 或分块传输时边读边检查大小，
+/// 不会先把整个响应读入内存
+pub async fn read_body(mut response: reqwest::Response, limit: usize) -> anyhow::Result<Bytes> {
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > limit)
+    {
+        anyhow::bail!("response too large");
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > limit {
+            anyhow::bail!("response too large");
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes.into())
+}
+
+/// 从 URL 获取所有可用的 favicon
+pub async fn fetch_favicons(client: &reqwest::Client, url: &str) -> Result<Vec<FetchedIcon>> {
+    // 保留端口，`host:port` 上的服务的图标不在默认端口
+    let base_url = match url::Url::parse(url) {
+        Ok(parsed) => parsed.origin().ascii_serialization(),
+        Err(_) => return Err(Error::BadRequest),
+    };
+
+    let mut icon_urls: Vec<String> = Vec::new();
+
+    // 1. 尝试解析 HTML 页面获取 link 标签中的图标
+    if let Ok(response) = client.get(url).send().await
+        && let Ok(html) = read_body(response, MAX_PAGE_BYTES).await
+    {
+        let document = Html::parse_document(&String::from_utf8_lossy(&html));
+
+        // 查找所有可能的图标链接
+        let selectors = [
+            r#"link[rel="icon"]"#,
+            r#"link[rel="shortcut icon"]"#,
+            r#"link[rel="apple-touch-icon"]"#,
+            r#"link[rel="apple-touch-icon-precomposed"]"#,
+            r#"link[rel="mask-icon"]"#,
+        ];
+
+        for selector_str in selectors {
+            if let Ok(selector) = Selector::parse(selector_str) {
+                for element in document.select(&selector) {
+                    if let Some(href) = element.value().attr("href") {
+                        let icon_url = resolve_url(&base_url, url, href);
+                        if !icon_urls.contains(&icon_url) {
+                            icon_urls.push(icon_url);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 2. 添加常见的 favicon 路径
+    let common_paths = [
+        "/favicon.ico",
+        "/favicon.png",
+        "/favicon.svg",
+        "/apple-touch-icon.png",
+        "/apple-touch-icon-precomposed.png",
+        "/favicon-32x32.png",
+        "/favicon-16x16.png",
+    ];
+
+    for path in common_paths {
+        let icon_url = format!("{base_url}{path}");
+        if !icon_urls.contains(&icon_url) {
+            icon_urls.push(icon_url);
+        }
+    }
+
+    // 3. 尝试获取每个图标
+    let mut icons: Vec<FetchedIcon> = Vec::new();
+
+    for icon_url in icon_urls {
+        if let Ok(response) = client.get(&icon_url).send().await
+            && response.status().is_success()
+        {
+            let content_type = response
+                .headers()
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("image/x-icon")
+                .to_string();
+
+            // 检查是否是图片类型
+            if (content_type.starts_with("image/") || content_type.contains("icon"))
+                && let Ok(bytes) = read_body(response, MAX_ICON_BYTES).await
+            {
+                // 验证是否是有效的图片
+                if is_valid_image(&bytes) {
+                    icons.push(FetchedIcon {
+                        url: icon_url,
+                        bytes,
+                        content_type,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(icons)
+}
+
+/// 从候选图标中挑选最合适的一个
+pub fn best_icon(icons: Vec<FetchedIcon>) -> Option<FetchedIcon> {
+    icons
+        .into_iter()
+        .filter(|icon| icon.extension().is_some())
+        .map(|icon| (icon.score(), icon))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, icon)| icon)
+}
+
+/// 解析相对 URL 为绝对 URL
+fn resolve_url(base_url: &str, page_url: &str, href: &str) -> String {
+    if href.starts_with("http://") || href.starts_with("https://") {
+        href.to_string()
+    } else if href.starts_with("//") {
+        format!("https:{href}")
+    } else if href.starts_with('/') {
+        format!("{base_url}{href}")
+    } else {
+        // 相对路径
+        if let Ok(base) = url::Url::parse(page_url)
+            && let Ok(resolved) = base.join(href)
+        {
+            return resolved.to_string();
+        }
+        format!("{base_url}/{href}")
+    }
+}
+
+/// SVG 是文本格式，检查 xml 或 svg 标签
+fn is_svg(bytes: &[u8]) -> bool {
+    if let Ok(text) = std::str::from_utf8(&bytes[..bytes.len().min(1000)]) {
+        let text_lower = text.to_lowercase();
+        return text_lower.contains("<svg") || text_lower.contains("<?xml");
+    }
+    false
+}
+
+/// 检查字节数据是否是有效的图片
+pub fn is_valid_image(bytes: &[u8]) -> bool {
+    // 检查常见图片格式的魔数
+    if bytes.len() < 4 {
+        return false;
+    }
+
+    // PNG
+    if bytes.starts_with(&[0x89, 0x50, 0x4E, 0x47]) {
+        return true;
+    }
+    // JPEG
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return true;
+    }
+    // GIF
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return true;
+    }
+    // ICO
+    if bytes.starts_with(&[0x00, 0x00, 0x01, 0x00]) {
+        return true;
+    }
+    // WebP
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return true;
+    }
+    // SVG
+    if is_svg(bytes) {
+        return true;
+    }
+    // BMP
+    if bytes.starts_with(b"BM") {
+        return true;
+    }
+
+    false
+}
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::api::{BACKGROUND_DIR, ICON_DIR};
+use crate::icon::icon_file;
+use crate::state::{AppState, Storage};
+
+/// Unreferenced assets wait here before they are deleted for good.
+pub const QUARANTINE_DIR: &str = "./config/quarantine";
+
+/// Recently written files may belong to an upload that is still being processed.
+const MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// Unreferenced files moved to quarantine.
+    pub quarantined_files: usize,
+    pub quarantined_bytes: u64,
+    /// Files deleted, either directly or after their quarantine expired.
+    pub deleted_files: usize,
+    pub reclaimed_bytes: u64,
+}
+
+/// Asset files referenced by any profile, the image library or the trash.
+fn referenced_files(storage: &Storage) -> (HashSet<String>, HashSet<String>) {
+    let bookmarks = storage
+        .profiles
+        .iter()
+        .flat_map(|p| &p.bookmarks)
+        .chain(storage.trash.iter().flat_map(|entry| entry.bookmarks()));
+    let icons = bookmarks
+        .filter_map(|b| b.icon.as_deref().and_then(icon_file))
+        .map(str::to_string)
+        .collect();
+    let backgrounds = storage
+        .images
+        .iter()
+        .chain(storage.profiles.iter().flat_map(|p| &p.background_images))
+        .chain(
+            storage
+                .trash
+                .iter()
+                .flat_map(|entry| entry.background_images()),
+        )
+        .flat_map(|bg| bg.files())
+        .map(str::to_string)
+        .collect();
+    (icons, backgrounds)
+}
+
+/// 清理没有被任何 profile 引用的图标和背景图
+pub async fn collect_garbage(state: &AppState, dry_run: bool) -> anyhow::Result<GcReport> {
+    // 持有锁，避免和上传同时进行
+    let storage = state.storage.lock().await;
+    let quarantine_days = storage.config.gc.quarantine_days;
+    let (icons, backgrounds) = referenced_files(&storage);
+
+    let mut report = GcReport {
+        dry_run,
+        ..Default::default()
+    };
+    for (dir, kind, referenced) in [
+        (ICON_DIR, "icons", &icons),
+        (BACKGROUND_DIR, "backgrounds", &backgrounds),
+    ] {
+        let quarantine = format!("{QUARANTINE_DIR}/{kind}");
+        sweep_dir(
+            dir,
+            &quarantine,
+            referenced,
+            quarantine_days,
+            dry_run,
+            &mut report,
+        )
+        .await?;
+        purge_quarantine(&quarantine, quarantine_days, dry_run, &mut report).await?;
+    }
+
+    Ok(report)
+}
+
+async fn sweep_dir(
+    dir: &str,
+    quarantine: &str,
+    referenced: &HashSet<String>,
+    quarantine_days: u64,
+    dry_run: bool,
+    report: &mut GcReport,
+) -> anyhow::Result<()> {
+    if !Path::new(dir).exists() {
+        return Ok(());
+    }
+
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        let filename = entry.file_name().to_string_lossy().to_string();
+        if !metadata.is_file() || referenced.contains(&filename) {
+            continue;
+        }
+        if metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age < MIN_AGE)
+        {
+            continue;
+        }
+
+        if quarantine_days == 0 {
+            report.deleted_files += 1;
+            report.reclaimed_bytes += metadata.len();
+            if !dry_run {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+        } else {
+            report.quarantined_files += 1;
+            report.quarantined_bytes += metadata.len();
+            if !dry_run {
+                // 文件名带上隔离时间，用于判断何时彻底删除
+                tokio::fs::create_dir_all(quarantine).await?;
+                let target = format!("{quarantine}/{}-{filename}", Utc::now().timestamp());
+                tokio::fs::rename(entry.path(), target).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn purge_quarantine(
+    quarantine: &str,
+    quarantine_days: u64,
+    dry_run: bool,
+    report: &mut GcReport,
+) -> anyhow::Result<()> {
+    if !Path::new(quarantine).exists() {
+        return Ok(());
+    }
+
+    let deadline = Utc::now().timestamp() - (quarantine_days * 24 * 60 * 60) as i64;
+    let mut entries = tokio::fs::read_dir(quarantine).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let filename = entry.file_name().to_string_lossy().to_string();
+        let Some(quarantined_at) = filename
+            .split_once('-')
+            .and_then(|(ts, _)| ts.parse::<i64>().ok())
+        else {
+            continue;
+        };
+        if quarantined_at > deadline {
+            continue;
+        }
+
+        let metadata = entry.metadata().await?;
+        report.deleted_files += 1;
+        report.reclaimed_bytes += metadata.len();
+        if !dry_run {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 将隔离中的文件移回原目录，文件已经在原目录时直接返回 `true`
+pub async fn recover_file(dir: &str, kind: &str, filename: &str) -> bool {
+    let path = format!("{dir}/{filename}");
+    if Path::new(&path).exists() {
+        return true;
+    }
+
+    let quarantine = format!("{QUARANTINE_DIR}/{kind}");
+    let Ok(mut entries) = tokio::fs::read_dir(&quarantine).await else {
+        return false;
+    };
+    let mut latest: Option<(i64, std::path::PathBuf)> = None;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some((ts, original)) = name.split_once('-')
+            && original == filename
+            && let Ok(ts) = ts.parse::<i64>()
+            && latest.as_ref().is_none_or(|(latest, _)| ts > *latest)
+        {
+            latest = Some((ts, entry.path()));
+        }
+    }
+    match latest {
+        Some((_, quarantined)) => tokio::fs::rename(quarantined, path).await.is_ok(),
+        None => false,
+    }
+}
+
+/// 定期清理未引用的资源文件
+pub async fn tick_gc(state: AppState) {
+    let interval_hours = state.storage.lock().await.config.gc.interval_hours;
+    if interval_hours == 0 {
+        return;
+    }
+
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(interval_hours * 60 * 60)).await;
+        match collect_garbage(&state, false).await {
+            Ok(report) => tracing::info!(
+                "Asset GC: {} files quarantined, {} files deleted, {} bytes reclaimed",
+                report.quarantined_files,
+                report.deleted_files,
+                report.reclaimed_bytes
+            ),
+            Err(err) => tracing::warn!("Asset GC failed: {err}"),
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::tls::TlsInfo;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::RwLock;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use crate::bookmark::Bookmark;
+use crate::events::EventKind;
+use crate::favicon;
+use crate::network::INTRANET_ZONE;
+use crate::state::AppState;
+
+/// Number of checks running at the same time.
+const CONCURRENCY: usize = 8;
+
+/// How a bookmark is checked, the defaults come from `HealthConfig`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthCheck {
+    pub enabled: bool,
+    /// `HEAD` or `GET`, some services answer `HEAD` with 405.
+    pub method: String,
+    /// Status codes counted as up, any 2xx or 3xx if empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expected_status: Vec<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            method: "GET".to_string(),
+            expected_status: vec![],
+            timeout_secs: None,
+        }
+    }
+}
+
+impl HealthCheck {
+    fn accepts(&self, status: u16) -> bool {
+        if self.expected_status.is_empty() {
+            (200..400).contains(&status)
+        } else {
+            self.expected_status.contains(&status)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HealthState {
+    Up,
+    Down,
+    Unknown,
+}
+
+/// The result of a single check.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthResult {
+    pub checked_at: DateTime<Utc>,
+    pub up: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The TLS certificate presented by an HTTPS link.
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificateInfo {
+    pub issuer: String,
+    pub subject: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// Issued by itself, browsers will warn unless it is trusted manually.
+    pub self_signed: bool,
+}
+
+impl CertificateInfo {
+    fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+        let validity = cert.validity();
+        Some(Self {
+            issuer: cert.issuer().to_string(),
+            subject: cert.subject().to_string(),
+            not_before: DateTime::from_timestamp(validity.not_before.timestamp(), 0)?,
+            not_after: DateTime::from_timestamp(validity.not_after.timestamp(), 0)?,
+            self_signed: cert.issuer() == cert.subject(),
+        })
+    }
+
+    /// Negative once the certificate has expired.
+    pub fn days_until_expiry(&self) -> i64 {
+        (self.not_after - Utc::now()).num_days()
+    }
+}
+
+/// Recent results for one link of a bookmark.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetHealth {
+    /// `None` for `url`, otherwise the network zone, e.g. `intranet` for `intranet_url`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    pub url: String,
+    /// Oldest first.
+    pub history: VecDeque<HealthResult>,
+    /// From the last successful HTTPS check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<CertificateInfo>,
+}
+
+impl TargetHealth {
+    pub fn state(&self) -> HealthState {
+        match self.history.back() {
+            Some(result) if result.up => HealthState::Up,
+            Some(_) => HealthState::Down,
+            None => HealthState::Unknown,
+        }
+    }
+
+    /// Share of successful checks in the history.
+    pub fn uptime(&self) -> Option<f64> {
+        if self.history.is_empty() {
+            return None;
+        }
+        let up = self.history.iter().filter(|r| r.up).count();
+        Some(up as f64 / self.history.len() as f64)
+    }
+}
+
+/// Results of the background health checks, kept in memory.
+#[derive(Debug, Clone, Default)]
+pub struct HealthMonitor {
+    targets: Arc<RwLock<HashMap<Uuid, Vec<TargetHealth>>>>,
+}
+
+impl HealthMonitor {
+    pub async fn get(&self, id: Uuid) -> Option<Vec<TargetHealth>> {
+        self.targets.read().await.get(&id).cloned()
+    }
+
+    /// Returns the previous and the new state of the target if the state changed.
+    async fn record(
+        &self,
+        id: Uuid,
+        zone: Option<String>,
+        url: String,
+        (result, certificate): (HealthResult, Option<CertificateInfo>),
+        history_len: usize,
+    ) -> Option<(HealthState, HealthState)> {
+        let mut targets = self.targets.write().await;
+        let target = target_mut(targets.entry(id).or_default(), zone, url);
+        let before = target.state();
+        if certificate.is_some() {
+            target.certificate = certificate;
+        }
+        target.history.push_back(result);
+        while target.history.len() > history_len.max(1) {
+            target.history.pop_front();
+        }
+        let after = target.state();
+        (before != after).then_some((before, after))
+    }
+
+    /// 只更新证书，不计入可用性记录
+    async fn record_certificate(
+        &self,
+        id: Uuid,
+        zone: Option<String>,
+        url: String,
+        certificate: CertificateInfo,
+    ) {
+        let mut targets = self.targets.write().await;
+        target_mut(targets.entry(id).or_default(), zone, url).certificate = Some(certificate);
+    }
+
+    /// 移除已删除或不再检查的书签和网络区域
+    async fn retain(&self, checks: &[CheckTarget]) {
+        self.targets.write().await.retain(|id, entries| {
+            entries.retain(|t| checks.iter().any(|c| c.id == *id && c.zone == t.zone));
+            !entries.is_empty()
+        });
+    }
+}
+
+fn target_mut(
+    entries: &mut Vec<TargetHealth>,
+    zone: Option<String>,
+    url: String,
+) -> &mut TargetHealth {
+    // 链接修改后重新开始记录
+    entries.retain(|t| t.zone != zone || t.url == url);
+    match entries.iter_mut().position(|t| t.zone == zone) {
+        Some(index) => &mut entries[index],
+        None => {
+            entries.push(TargetHealth {
+                zone,
+                url,
+                history: VecDeque::new(),
+                certificate: None,
+            });
+            entries.last_mut().unwrap()
+        }
+    }
+}
+
+struct CheckTarget {
+    id: Uuid,
+    zone: Option<String>,
+    url: String,
+    check: HealthCheck,
+}
+
+fn check_targets(bookmark: &Bookmark) -> Vec<CheckTarget> {
+    let check = bookmark.health_check.clone().unwrap_or_default();
+    if !check.enabled {
+        return vec![];
+    }
+
+    let mut targets = vec![CheckTarget {
+        id: bookmark.id,
+        zone: None,
+        url: bookmark.url.clone(),
+        check: check.clone(),
+    }];
+    if let Some(intranet_url) = &bookmark.intranet_url {
+        targets.push(CheckTarget {
+            id: bookmark.id,
+            zone: Some(INTRANET_ZONE.to_string()),
+            url: intranet_url.clone(),
+            check: check.clone(),
+        });
+    }
+    for (zone, url) in &bookmark.zone_urls {
+        targets.push(CheckTarget {
+            id: bookmark.id,
+            zone: Some(zone.clone()),
+            url: url.clone(),
+            check: check.clone(),
+        });
+    }
+    targets
+}
+
+async fn probe(
+    client: &reqwest::Client,
+    target: &CheckTarget,
+    default_timeout: Duration,
+) -> (HealthResult, Option<CertificateInfo>) {
+    let method = reqwest::Method::from_bytes(target.check.method.to_ascii_uppercase().as_bytes())
+        .unwrap_or(reqwest::Method::GET);
+    let timeout = target
+        .check
+        .timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(default_timeout);
+
+    let started = Instant::now();
+    let response = client
+        .request(method, &target.url)
+        .timeout(timeout)
+        .send()
+        .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match response {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let certificate = response
+                .extensions()
+                .get::<TlsInfo>()
+                .and_then(TlsInfo::peer_certificate)
+                .and_then(CertificateInfo::from_der);
+            let result = HealthResult {
+                checked_at: Utc::now(),
+                up: target.check.accepts(status),
+                status: Some(status),
+                latency_ms,
+                error: None,
+            };
+            (result, certificate)
+        }
+        Err(err) => {
+            let result = HealthResult {
+                checked_at: Utc::now(),
+                up: false,
+                status: None,
+                latency_ms,
+                error: Some(err.to_string()),
+            };
+            (result, None)
+        }
+    }
+}
+
+async fn all_targets(state: &AppState) -> Vec<CheckTarget> {
+    let storage = state.storage.lock().await;
+    storage
+        .profiles
+        .iter()
+        .flat_map(|p| &p.bookmarks)
+        .flat_map(check_targets)
+        .collect()
+}
+
+/// 自签名证书的服务也算正常，证书的问题单独记录
+fn check_client() -> Option<reqwest::Client> {
+    favicon::http_client_builder()
+        .tls_info(true)
+        .tls_danger_accept_invalid_certs(true)
+        .build()
+        .ok()
+}
+
+/// 同时检查多个链接，按完成的顺序处理结果
+async fn probe_all<F, Fut>(
+    client: &reqwest::Client,
+    targets: Vec<CheckTarget>,
+    default_timeout: Duration,
+    mut handle: F,
+) where
+    F: FnMut(CheckTarget, (HealthResult, Option<CertificateInfo>)) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut targets = targets.into_iter();
+    let mut running = JoinSet::new();
+    loop {
+        while running.len() < CONCURRENCY
+            && let Some(target) = targets.next()
+        {
+            let client = client.clone();
+            running.spawn(async move {
+                let result = probe(&client, &target, default_timeout).await;
+                (target, result)
+            });
+        }
+        let Some(joined) = running.join_next().await else {
+            break;
+        };
+        if let Ok((target, result)) = joined {
+            handle(target, result).await;
+        }
+    }
+}
+
+/// 定期检查书签对应的服务是否可用
+pub async fn tick_health_checks(state: AppState) {
+    let config = state.storage.lock().await.config.health.clone();
+    if config.interval_secs == 0 {
+        return;
+    }
+    let Some(client) = check_client() else {
+        return;
+    };
+    let default_timeout = Duration::from_secs(config.timeout_secs);
+
+    loop {
+        let targets = all_targets(&state).await;
+        state.health.retain(&targets).await;
+
+        probe_all(&client, targets, default_timeout, |target, result| {
+            let state = &state;
+            async move {
+                let Some((_, health_state)) = state
+                    .health
+                    .record(
+                        target.id,
+                        target.zone.clone(),
+                        target.url,
+                        result,
+                        config.history_len,
+                    )
+                    .await
+                else {
+                    return;
+                };
+                let profile = state
+                    .storage
+                    .lock()
+                    .await
+                    .bookmark_profile(target.id)
+                    .map(str::to_string);
+                state.events.publish(
+                    EventKind::Status,
+                    profile.as_deref(),
+                    json!({ "id": target.id, "zone": target.zone, "state": health_state }),
+                );
+            }
+        })
+        .await;
+
+        tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+    }
+}
+
+/// 定期检查 HTTPS 链接的证书，关闭可用性检查时也会运行
+pub async fn tick_certificate_checks(state: AppState) {
+    let config = state.storage.lock().await.config.health.clone();
+    if config.cert_interval_hours == 0 {
+        return;
+    }
+    let Some(client) = check_client() else {
+        return;
+    };
+    let default_timeout = Duration::from_secs(config.timeout_secs);
+
+    loop {
+        let targets = all_targets(&state).await;
+        state.health.retain(&targets).await;
+        let targets = targets
+            .into_iter()
+            .filter(|t| t.url.starts_with("https://"))
+            .collect();
+
+        probe_all(
+            &client,
+            targets,
+            default_timeout,
+            |target, (_, certificate)| {
+                let health = &state.health;
+                async move {
+                    if let Some(certificate) = certificate {
+                        health
+                            .record_certificate(target.id, target.zone, target.url, certificate)
+                            .await;
+                    }
+                }
+            },
+        )
+        .await;
+
+        tokio::time::sleep(Duration::from_secs(config.cert_interval_hours * 60 * 60)).await;
+    }
+}
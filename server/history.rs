@@ -0,0 +1,371 @@
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::fs;
+use std::io::Write;
+use std::net::IpAddr;
+
+use anyhow::Result;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::bookmark::{Bookmark, BookmarkProfile};
+use crate::config::HistoryConfig;
+use crate::network::Device;
+use crate::state::AppState;
+
+const HISTORY_PATH: &str = "./config/history.jsonl";
+
+/// Who made a change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    /// The start of the session id, tells devices apart when logged in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    pub address: IpAddr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+impl FromRequestParts<AppState> for Actor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        // 经过受信代理时记录真实的客户端地址
+        let device = Device::from_request_parts(parts, state).await?;
+        // 只记录会话 ID 的前几位，历史记录不能用来登录
+        let session = CookieJar::from_headers(&parts.headers)
+            .get("session_id")
+            .map(|cookie| cookie.value().chars().take(8).collect());
+        Ok(Self {
+            session,
+            address: device.address,
+            user_agent: device.user_agent,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    Create,
+    Update,
+    Delete,
+    Rename,
+    LinkAction,
+    Restore,
+    Undo,
+    Redo,
+}
+
+/// A bookmark before and after a change, `None` if it did not exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookmarkChange {
+    pub id: Uuid,
+    pub before: Option<Bookmark>,
+    pub after: Option<Bookmark>,
+}
+
+/// The profile without its bookmarks, `None` if it did not exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsChange {
+    pub before: Option<BookmarkProfile>,
+    pub after: Option<BookmarkProfile>,
+}
+
+/// Bookmark ids in order, only recorded when the order or the set of bookmarks changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderChange {
+    pub before: Vec<Uuid>,
+    pub after: Vec<Uuid>,
+}
+
+/// A single mutation of a profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub id: u64,
+    pub profile: String,
+    pub at: DateTime<Utc>,
+    pub actor: Actor,
+    pub action: HistoryAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<SettingsChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bookmarks: Vec<BookmarkChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<OrderChange>,
+}
+
+impl Revision {
+    pub fn touches(&self, bookmark: Uuid) -> bool {
+        self.bookmarks.iter().any(|change| change.id == bookmark)
+    }
+
+    /// 撤销这次修改，`profile` 为修改后的状态
+    fn undo(&self, profile: &mut Option<BookmarkProfile>) {
+        if let Some(settings) = &self.settings {
+            *profile = settings.before.clone().map(|before| BookmarkProfile {
+                bookmarks: profile.take().map(|p| p.bookmarks).unwrap_or_default(),
+                ..before
+            });
+        }
+        let Some(profile) = profile else {
+            return;
+        };
+        for change in &self.bookmarks {
+            let index = profile.bookmarks.iter().position(|b| b.id == change.id);
+            match (index, &change.before) {
+                (Some(index), Some(before)) => profile.bookmarks[index] = before.clone(),
+                (Some(index), None) => {
+                    profile.bookmarks.remove(index);
+                }
+                (None, Some(before)) => profile.bookmarks.push(before.clone()),
+                (None, None) => {}
+            }
+        }
+        if let Some(order) = &self.order {
+            profile.bookmarks.sort_by_key(|b| {
+                order
+                    .before
+                    .iter()
+                    .position(|id| *id == b.id)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+    }
+}
+
+fn settings_of(profile: &BookmarkProfile) -> BookmarkProfile {
+    BookmarkProfile {
+        bookmarks: vec![],
+        ..profile.clone()
+    }
+}
+
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Append-only log of profile mutations, stored as JSON lines.
+#[derive(Default)]
+pub struct History {
+    revisions: VecDeque<Revision>,
+    next_id: u64,
+}
+
+impl Debug for History {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} revisions", self.revisions.len())
+    }
+}
+
+impl History {
+    pub fn load() -> Result<Self> {
+        let mut revisions = VecDeque::new();
+        if let Ok(content) = fs::read_to_string(HISTORY_PATH) {
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                // 写入中断的最后一行无法解析，跳过
+                match serde_json::from_str::<Revision>(line) {
+                    Ok(revision) => revisions.push_back(revision),
+                    Err(err) => tracing::warn!("Skipping invalid history entry: {err}"),
+                }
+            }
+        }
+        let next_id = revisions.iter().map(|r| r.id + 1).max().unwrap_or(1);
+        Ok(Self { revisions, next_id })
+    }
+
+    /// Revisions of the profile, newest first.
+    pub fn revisions<'a>(&'a self, profile: &'a str) -> impl Iterator<Item = &'a Revision> {
+        self.revisions
+            .iter()
+            .rev()
+            .filter(move |r| r.profile == profile)
+    }
+
+    /// 记录 profile 的修改，没有变化时不记录
+    pub fn record(
+        &mut self,
+        config: &HistoryConfig,
+        actor: Actor,
+        action: HistoryAction,
+        before: Option<&BookmarkProfile>,
+        after: Option<&BookmarkProfile>,
+    ) -> Result<()> {
+        let Some(profile) = after.or(before).map(|p| p.name.clone()) else {
+            return Ok(());
+        };
+
+        let settings_before = before.map(settings_of);
+        let settings_after = after.map(settings_of);
+        let settings = (!same(&settings_before, &settings_after)).then_some(SettingsChange {
+            before: settings_before,
+            after: settings_after,
+        });
+
+        let old = before.map(|p| p.bookmarks.as_slice()).unwrap_or_default();
+        let new = after.map(|p| p.bookmarks.as_slice()).unwrap_or_default();
+        let mut bookmarks = vec![];
+        for bookmark in old {
+            let after = new.iter().find(|b| b.id == bookmark.id);
+            if after.is_none_or(|after| !same(bookmark, after)) {
+                bookmarks.push(BookmarkChange {
+                    id: bookmark.id,
+                    before: Some(bookmark.clone()),
+                    after: after.cloned(),
+                });
+            }
+        }
+        for bookmark in new.iter().filter(|b| !old.iter().any(|o| o.id == b.id)) {
+            bookmarks.push(BookmarkChange {
+                id: bookmark.id,
+                before: None,
+                after: Some(bookmark.clone()),
+            });
+        }
+
+        let order_before: Vec<Uuid> = old.iter().map(|b| b.id).collect();
+        let order_after: Vec<Uuid> = new.iter().map(|b| b.id).collect();
+        let order = (order_before != order_after).then_some(OrderChange {
+            before: order_before,
+            after: order_after,
+        });
+
+        if settings.is_none() && bookmarks.is_empty() && order.is_none() {
+            return Ok(());
+        }
+
+        let revision = Revision {
+            id: self.next_id,
+            profile,
+            at: Utc::now(),
+            actor,
+            action,
+            settings,
+            bookmarks,
+            order,
+        };
+        self.next_id += 1;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(HISTORY_PATH)?;
+        writeln!(file, "{}", serde_json::to_string(&revision)?)?;
+        self.revisions.push_back(revision);
+
+        if self.prune(config) {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Drop revisions beyond the retention limits, returns whether any were dropped.
+    fn prune(&mut self, config: &HistoryConfig) -> bool {
+        let len = self.revisions.len();
+        if config.max_age_days > 0 {
+            let cutoff = Utc::now() - Duration::days(config.max_age_days as i64);
+            self.revisions.retain(|r| r.at >= cutoff);
+        }
+        if config.max_revisions > 0 {
+            // 从新到旧计数，每个 profile 只保留最近的 max_revisions 条
+            let mut counts = HashMap::<&str, usize>::new();
+            let keep: Vec<bool> = self
+                .revisions
+                .iter()
+                .rev()
+                .map(|r| {
+                    let count = counts.entry(&r.profile).or_default();
+                    *count += 1;
+                    *count <= config.max_revisions
+                })
+                .collect();
+            let mut keep = keep.into_iter().rev();
+            self.revisions.retain(|_| keep.next().unwrap_or(true));
+        }
+        self.revisions.len() != len
+    }
+
+    fn save(&self) -> Result<()> {
+        let mut content = String::new();
+        for revision in &self.revisions {
+            content.push_str(&serde_json::to_string(revision)?);
+            content.push('\n');
+        }
+        fs::write(HISTORY_PATH, content)?;
+        Ok(())
+    }
+
+    /// 重命名后历史记录跟随新的名称
+    pub fn rename_profile(&mut self, name: &str, new_name: &str) -> Result<()> {
+        let mut renamed = vec![];
+        for revision in self.revisions.iter_mut().filter(|r| r.profile == name) {
+            revision.profile = new_name.to_string();
+            renamed.push(revision.id);
+        }
+        if !renamed.is_empty()
+            && let Err(err) = self.save()
+        {
+            // 保存失败时恢复原名，和文件保持一致
+            for revision in self.revisions.iter_mut() {
+                if renamed.contains(&revision.id) {
+                    revision.profile = name.to_string();
+                }
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// The profile as it was right after the revision, `None` if the revision is unknown or
+    /// deleted the profile.
+    pub fn profile_at(
+        &self,
+        current: Option<&BookmarkProfile>,
+        profile: &str,
+        revision: u64,
+    ) -> Option<BookmarkProfile> {
+        if !self.revisions(profile).any(|r| r.id == revision) {
+            return None;
+        }
+        let mut state = current.cloned();
+        for newer in self.revisions(profile).take_while(|r| r.id > revision) {
+            newer.undo(&mut state);
+        }
+        state.map(|state| BookmarkProfile {
+            name: profile.to_string(),
+            ..state
+        })
+    }
+
+    /// The bookmark as it was right after the revision, `Some(None)` if it did not exist then.
+    pub fn bookmark_at(
+        &self,
+        current: Option<&Bookmark>,
+        profile: &str,
+        revision: u64,
+        id: Uuid,
+    ) -> Option<Option<Bookmark>> {
+        if !self.revisions(profile).any(|r| r.id == revision) {
+            return None;
+        }
+        // 之后第一次修改前的状态就是当时的状态
+        let state = match self
+            .revisions(profile)
+            .take_while(|r| r.id > revision)
+            .filter_map(|r| r.bookmarks.iter().find(|change| change.id == id))
+            .last()
+        {
+            Some(change) => change.before.clone(),
+            None => current.cloned(),
+        };
+        Some(state)
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::{Mutex, Notify};
+use uuid::Uuid;
+
+use crate::api::ICON_DIR;
+use crate::avatar::{self, GENERATED_ICON};
+use crate::bookmark::{Bookmark, IconSource};
+use crate::error::Result;
+use crate::events::EventKind;
+use crate::favicon;
+use crate::icon_library;
+use crate::state::AppState;
+
+/// The file name in `ICON_DIR` of a `Bookmark.icon` value, if it is a file.
+pub fn icon_file(icon: &str) -> Option<&str> {
+    if avatar::is_generated(icon) || icon_library::library_path(icon).is_some() {
+        None
+    } else {
+        Some(icon)
+    }
+}
+
+/// Remove the icon file of the bookmark, if any.
+pub async fn remove_icon_file(bookmark: &Bookmark) {
+    if let Some(filename) = bookmark.icon.as_deref().and_then(icon_file) {
+        let _ = tokio::fs::remove_file(format!("{ICON_DIR}/{filename}")).await;
+    }
+}
+
+/// Point the bookmark at a new icon file in `ICON_DIR`, removing the old one.
+pub async fn replace_icon(bookmark: &mut Bookmark, icon_filename: String, source: IconSource) {
+    if bookmark.icon.as_deref() != Some(&icon_filename) {
+        remove_icon_file(bookmark).await;
+    }
+    bookmark.icon = Some(icon_filename);
+    bookmark.icon_source = source;
+}
+
+/// Write an icon file into `ICON_DIR` and point the bookmark at it.
+pub async fn store_icon(
+    bookmark: &mut Bookmark,
+    ext: &str,
+    content: &[u8],
+    source: IconSource,
+) -> Result<()> {
+    tokio::fs::create_dir_all(ICON_DIR).await?;
+
+    let icon_filename = format!("{}.{ext}", bookmark.id);
+    tokio::fs::write(format!("{ICON_DIR}/{icon_filename}"), content).await?;
+    replace_icon(bookmark, icon_filename, source).await;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IconFetchState {
+    Pending,
+    Fetching,
+    Done,
+    /// The bookmark got a manual icon or was removed in the meantime.
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IconFetchStatus {
+    pub state: IconFetchState,
+    pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl IconFetchStatus {
+    fn new(state: IconFetchState) -> Self {
+        Self {
+            state,
+            updated_at: Utc::now(),
+            error: None,
+        }
+    }
+}
+
+/// Queue of bookmarks waiting for an automatic favicon fetch.
+#[derive(Debug, Clone, Default)]
+pub struct IconJobs {
+    statuses: Arc<Mutex<HashMap<Uuid, IconFetchStatus>>>,
+    notify: Arc<Notify>,
+}
+
+impl IconJobs {
+    pub async fn enqueue(&self, ids: impl IntoIterator<Item = Uuid>) {
+        let mut statuses = self.statuses.lock().await;
+        let mut queued = false;
+        for id in ids {
+            statuses.insert(id, IconFetchStatus::new(IconFetchState::Pending));
+            queued = true;
+        }
+        if queued {
+            self.notify.notify_one();
+        }
+    }
+
+    pub async fn status(&self, id: Uuid) -> Option<IconFetchStatus> {
+        self.statuses.lock().await.get(&id).cloned()
+    }
+
+    async fn set_status(&self, id: Uuid, status: IconFetchStatus) {
+        self.statuses.lock().await.insert(id, status);
+    }
+
+    async fn take_pending(&self) -> Vec<Uuid> {
+        let mut statuses = self.statuses.lock().await;
+        let mut pending = Vec::new();
+        for (id, status) in statuses.iter_mut() {
+            if status.state == IconFetchState::Pending {
+                *status = IconFetchStatus::new(IconFetchState::Fetching);
+                pending.push(*id);
+            }
+        }
+        pending
+    }
+}
+
+/// Whether the bookmark may receive an automatically fetched icon.
+pub fn accepts_auto_icon(bookmark: &Bookmark) -> bool {
+    bookmark.icon.is_none() || bookmark.icon_source == IconSource::Fetched
+}
+
+/// Bookmarks in `new` that need an icon fetch compared to `old`.
+pub fn icons_to_fetch(old: &[Bookmark], new: &[Bookmark]) -> Vec<Uuid> {
+    new.iter()
+        .filter(|b| match old.iter().find(|o| o.id == b.id) {
+            None => b.icon.is_none(),
+            Some(o) => o.url != b.url && accepts_auto_icon(b),
+        })
+        .map(|b| b.id)
+        .collect()
+}
+
+/// 后台获取书签图标
+pub async fn icon_worker(state: AppState) {
+    loop {
+        state.icon_jobs.notify.notified().await;
+        for id in state.icon_jobs.take_pending().await {
+            let status = match fetch_icon(&state, id).await {
+                Ok(true) => IconFetchStatus::new(IconFetchState::Done),
+                Ok(false) => IconFetchStatus::new(IconFetchState::Skipped),
+                Err(err) => {
+                    tracing::warn!("Failed to fetch icon for bookmark {id}: {err}");
+                    IconFetchStatus {
+                        error: Some(err.to_string()),
+                        ..IconFetchStatus::new(IconFetchState::Failed)
+                    }
+                }
+            };
+            state.icon_jobs.set_status(id, status).await;
+        }
+    }
+}
+
+/// Returns `Ok(false)` when the bookmark no longer wants an automatic icon.
+async fn fetch_icon(state: &AppState, id: Uuid) -> anyhow::Result<bool> {
+    let url = {
+        let storage = state.storage.lock().await;
+        match storage.get_bookmark(id) {
+            Some(bookmark) if accepts_auto_icon(bookmark) => bookmark.url.clone(),
+            _ => return Ok(false),
+        }
+    };
+
+    let client = favicon::http_client()?;
+    let icons = favicon::fetch_favicons(&client, &url).await?;
+    let Some(icon) = favicon::best_icon(icons) else {
+        // 网站没有图标时退回到字母头像，之后刷新时仍会重新尝试
+        let mut storage = state.storage.lock().await;
+        if let Some(bookmark) = storage.get_bookmark_mut(id)
+            && bookmark.url == url
+            && bookmark.icon.is_none()
+        {
+            bookmark.icon = Some(GENERATED_ICON.to_string());
+            bookmark.icon_source = IconSource::Fetched;
+            storage.save_profiles().await?;
+            state.events.publish(
+                EventKind::Bookmark,
+                storage.bookmark_profile(id),
+                json!({ "ids": [id] }),
+            );
+        }
+        anyhow::bail!("No icon found");
+    };
+    let Some(ext) = icon.extension() else {
+        anyhow::bail!("Unsupported icon format");
+    };
+
+    let mut storage = state.storage.lock().await;
+    let Some(bookmark) = storage.get_bookmark_mut(id) else {
+        return Ok(false);
+    };
+    // The url may have changed while we were fetching.
+    if bookmark.url != url || !accepts_auto_icon(bookmark) {
+        return Ok(false);
+    }
+    store_icon(bookmark, ext, &icon.bytes, IconSource::Fetched).await?;
+    storage.save_profiles().await?;
+    state.events.publish(
+        EventKind::Bookmark,
+        storage.bookmark_profile(id),
+        json!({ "ids": [id] }),
+    );
+
+    Ok(true)
+}
+
+/// 定期刷新自动获取的图标
+pub async fn tick_refresh_icons(state: AppState) {
+    let interval_hours = state
+        .storage
+        .lock()
+        .await
+        .config
+        .icon
+        .refresh_interval_hours;
+    if interval_hours == 0 {
+        return;
+    }
+
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(interval_hours * 60 * 60)).await;
+        let ids: Vec<Uuid> = {
+            let storage = state.storage.lock().await;
+            storage
+                .profiles
+                .iter()
+                .flat_map(|p| &p.bookmarks)
+                .filter(|b| b.icon.is_some() && b.icon_source == IconSource::Fetched)
+                .map(|b| b.id)
+                .collect()
+        };
+        state.icon_jobs.enqueue(ids).await;
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+/// Prefix of `Bookmark.icon` values that point into the icon library.
+pub const LIBRARY_ICON_PREFIX: &str = "library:";
+
+/// Preferred formats first.
+const EXTENSIONS: [&str; 6] = ["svg", "png", "webp", "jpg", "jpeg", "ico"];
+
+/// Icon files with the same name, e.g. `svg/jellyfin.svg` and `png/jellyfin.png`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryIcon {
+    pub name: String,
+    /// Best variant, relative to the library directory.
+    pub path: String,
+    pub variants: Vec<String>,
+}
+
+/// Index of a locally mounted icon pack such as dashboard-icons or simple-icons.
+#[derive(Debug, Clone, Default)]
+pub struct IconLibrary {
+    dir: Option<PathBuf>,
+    icons: Arc<RwLock<Vec<LibraryIcon>>>,
+}
+
+impl IconLibrary {
+    pub fn new(dir: Option<&str>) -> Self {
+        let dir = dir.map(PathBuf::from);
+        let icons = dir.as_deref().map(scan).unwrap_or_default();
+        if let Some(dir) = &dir {
+            tracing::info!("Indexed {} icons in {}", icons.len(), dir.display());
+        }
+        Self {
+            dir,
+            icons: Arc::new(RwLock::new(icons)),
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Rescan the library directory, returns the number of icons.
+    pub async fn reindex(&self) -> usize {
+        let Some(dir) = self.dir.clone() else {
+            return 0;
+        };
+        let icons = tokio::task::spawn_blocking(move || scan(&dir))
+            .await
+            .unwrap_or_default();
+        let count = icons.len();
+        *self.icons.write().await = icons;
+        count
+    }
+
+    /// 按名称搜索图标，完全匹配优先，其次是前缀匹配和包含匹配
+    pub async fn search(&self, query: &str, limit: usize) -> Vec<LibraryIcon> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let icons = self.icons.read().await;
+        let mut matches: Vec<(u8, &LibraryIcon)> = icons
+            .iter()
+            .filter_map(|icon| {
+                let name = normalize(&icon.name);
+                let rank = if name == query {
+                    0
+                } else if name.starts_with(&query) {
+                    1
+                } else if name.contains(&query) {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, icon))
+            })
+            .collect();
+        matches.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then(a.name.cmp(&b.name))
+        });
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, icon)| icon.clone())
+            .collect()
+    }
+
+    /// Whether `path` is a file known to the index.
+    pub async fn contains(&self, path: &str) -> bool {
+        self.icons
+            .read()
+            .await
+            .iter()
+            .any(|icon| icon.variants.iter().any(|v| v == path))
+    }
+}
+
+/// The library path of a `Bookmark.icon` value, if it points into the library.
+pub fn library_path(icon: &str) -> Option<&str> {
+    icon.strip_prefix(LIBRARY_ICON_PREFIX)
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn scan(dir: &Path) -> Vec<LibraryIcon> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files);
+
+    // 同名图标按格式优先级合并
+    let mut by_name: BTreeMap<String, Vec<(usize, String)>> = BTreeMap::new();
+    for (name, priority, path) in files {
+        by_name.entry(name).or_default().push((priority, path));
+    }
+
+    by_name
+        .into_iter()
+        .map(|(name, mut variants)| {
+            variants.sort();
+            let variants: Vec<String> = variants.into_iter().map(|(_, path)| path).collect();
+            LibraryIcon {
+                name,
+                path: variants[0].clone(),
+                variants,
+            }
+        })
+        .collect()
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, usize, String)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        tracing::warn!("Failed to read icon library directory {}", dir.display());
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // 跳过 .git 等隐藏目录
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_files(root, &path, files);
+            continue;
+        }
+        // 不进入指向目录的符号链接，链接成环时会无限递归
+        if path.is_dir() {
+            continue;
+        }
+
+        let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        let ext = ext.to_lowercase();
+        let Some(priority) = EXTENSIONS.iter().position(|e| *e == ext) else {
+            continue;
+        };
+        let (Some(name), Ok(relative)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            path.strip_prefix(root),
+        ) else {
+            continue;
+        };
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((name.to_string(), priority, relative));
+    }
+}
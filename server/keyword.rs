@@ -0,0 +1,91 @@
+use url::Url;
+use uuid::Uuid;
+
+use crate::bookmark::{Bookmark, BookmarkProfile};
+use crate::error::{Error, Result};
+
+/// 关键字只能包含字母、数字和 `-_.`，不能是 UUID，以免和 `/go/{id}` 混淆
+fn validate(keyword: &str) -> Result<()> {
+    let valid_chars = keyword
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if keyword.is_empty() || !valid_chars || Uuid::parse_str(keyword).is_ok() {
+        return Err(Error::InvalidKeyword(keyword.to_string()));
+    }
+    Ok(())
+}
+
+/// 检查 profile 中的关键字，不能在 profile 内重复，也不能和其他 profile 中的关键字相同
+pub fn check_keywords(profiles: &[BookmarkProfile], profile: &BookmarkProfile) -> Result<()> {
+    let others = profiles
+        .iter()
+        .filter(|p| p.name != profile.name)
+        .flat_map(|p| &p.bookmarks);
+    let mut seen: Vec<&str> = others.filter_map(|b| b.keyword.as_deref()).collect();
+    for keyword in profile
+        .bookmarks
+        .iter()
+        .filter_map(|b| b.keyword.as_deref())
+    {
+        validate(keyword)?;
+        if seen.iter().any(|k| k.eq_ignore_ascii_case(keyword)) {
+            return Err(Error::KeywordConflict(keyword.to_string()));
+        }
+        seen.push(keyword);
+    }
+    Ok(())
+}
+
+/// 查找 `/go/{target}` 指向的书签，`target` 为书签 ID 或关键字（不区分大小写）
+pub fn find<'a>(
+    profiles: &'a [BookmarkProfile],
+    target: &str,
+) -> Option<(&'a BookmarkProfile, &'a Bookmark)> {
+    let id = Uuid::parse_str(target).ok();
+    profiles.iter().find_map(|profile| {
+        profile
+            .bookmarks
+            .iter()
+            .find(|b| match id {
+                Some(id) => b.id == id,
+                None => b
+                    .keyword
+                    .as_deref()
+                    .is_some_and(|k| k.eq_ignore_ascii_case(target)),
+            })
+            .map(|bookmark| (profile, bookmark))
+    })
+}
+
+/// 把 `/go/{keyword}/` 之后的路径代入链接中的 `{}` 占位符，没有占位符时追加到链接的路径后，
+/// 查询参数追加到链接的查询参数后。`rest` 和 `query` 保持请求中的编码
+pub fn expand(url: &str, rest: &str, query: Option<&str>) -> String {
+    let query = query.filter(|q| !q.is_empty());
+    if url.contains("{}") {
+        let expanded = url.replace("{}", rest);
+        return match query {
+            Some(query) if expanded.contains('?') => format!("{expanded}&{query}"),
+            Some(query) => format!("{expanded}?{query}"),
+            None => expanded,
+        };
+    }
+    if rest.is_empty() && query.is_none() {
+        return url.to_string();
+    }
+
+    let Ok(mut parsed) = Url::parse(url) else {
+        return format!("{}/{rest}", url.trim_end_matches('/'));
+    };
+    if !rest.is_empty() {
+        let path = format!("{}/{rest}", parsed.path().trim_end_matches('/'));
+        parsed.set_path(&path);
+    }
+    if let Some(query) = query {
+        let merged = match parsed.query() {
+            Some(existing) if !existing.is_empty() => format!("{existing}&{query}"),
+            _ => query.to_string(),
+        };
+        parsed.set_query(Some(&merged));
+    }
+    parsed.to_string()
+}
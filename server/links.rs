@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::error::Error as _;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use url::Url;
+use uuid::Uuid;
+
+use crate::events::EventKind;
+use crate::favicon;
+use crate::state::AppState;
+
+/// Number of links checked at the same time.
+const CONCURRENCY: usize = 8;
+
+/// Redirects followed to find the final target.
+const MAX_REDIRECTS: usize = 5;
+
+/// Outcome of checking a single bookmark url.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum LinkStatus {
+    Ok {
+        status: u16,
+    },
+    /// `target` is where the redirects end.
+    Redirect {
+        status: u16,
+        target: String,
+        /// Every redirect on the way is permanent, e.g. 301 or 308.
+        permanent: bool,
+    },
+    ClientError {
+        status: u16,
+    },
+    ServerError {
+        status: u16,
+    },
+    DnsFailure {
+        error: String,
+    },
+    Timeout,
+    ConnectionError {
+        error: String,
+    },
+}
+
+impl LinkStatus {
+    pub fn is_broken(&self) -> bool {
+        !matches!(self, LinkStatus::Ok { .. } | LinkStatus::Redirect { .. })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkResult {
+    pub id: Uuid,
+    pub title: String,
+    pub url: String,
+    #[serde(flatten)]
+    pub status: LinkStatus,
+    /// Not reachable, candidates for `tag_broken` or `delete`.
+    pub broken: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkReport {
+    pub started_at: DateTime<Utc>,
+    /// `None` while the audit is running.
+    pub finished_at: Option<DateTime<Utc>>,
+    pub total: usize,
+    pub results: Vec<LinkResult>,
+}
+
+impl LinkReport {
+    pub fn result(&self, id: Uuid) -> Option<&LinkResult> {
+        self.results.iter().find(|r| r.id == id)
+    }
+}
+
+/// Bulk actions on the bookmarks of a link report.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkAction {
+    /// Replace the url with the redirect target, only for permanent redirects.
+    UpdateRedirect,
+    TagBroken,
+    Delete,
+}
+
+/// The latest link audit of each profile, kept in memory.
+#[derive(Debug, Clone, Default)]
+pub struct LinkAudits {
+    reports: Arc<Mutex<HashMap<String, LinkReport>>>,
+}
+
+impl LinkAudits {
+    pub async fn report(&self, profile: &str) -> Option<LinkReport> {
+        self.reports.lock().await.get(profile).cloned()
+    }
+}
+
+/// 开始检查 profile 中所有书签的链接，已有检查在进行时直接返回
+pub async fn start_audit(state: &AppState, profile: &str) -> Option<LinkReport> {
+    let bookmarks: Vec<(Uuid, String, String)> = {
+        let storage = state.storage.lock().await;
+        storage
+            .get_profile(profile)?
+            .bookmarks
+            .iter()
+            .map(|b| (b.id, b.title.clone(), b.url.clone()))
+            .collect()
+    };
+
+    let mut reports = state.link_audits.reports.lock().await;
+    if let Some(report) = reports.get(profile)
+        && report.finished_at.is_none()
+    {
+        return Some(report.clone());
+    }
+    let report = LinkReport {
+        started_at: Utc::now(),
+        finished_at: None,
+        total: bookmarks.len(),
+        results: vec![],
+    };
+    reports.insert(profile.to_string(), report.clone());
+    drop(reports);
+
+    tokio::spawn(run_audit(state.clone(), profile.to_string(), bookmarks));
+    Some(report)
+}
+
+async fn run_audit(state: AppState, profile: String, bookmarks: Vec<(Uuid, String, String)>) {
+    // 手动处理重定向，才能区分永久重定向和最终地址
+    let client = favicon::http_client_builder()
+        .redirect(Policy::none())
+        .build()
+        .ok();
+
+    let mut bookmarks = bookmarks.into_iter();
+    let mut running = JoinSet::new();
+    loop {
+        while running.len() < CONCURRENCY
+            && let Some((id, title, url)) = bookmarks.next()
+        {
+            let client = client.clone();
+            running.spawn(async move {
+                let status = match client {
+                    Some(client) => check_link(&client, &url).await,
+                    None => LinkStatus::ConnectionError {
+                        error: "failed to build HTTP client".to_string(),
+                    },
+                };
+                LinkResult {
+                    id,
+                    title,
+                    url,
+                    broken: status.is_broken(),
+                    status,
+                }
+            });
+        }
+        let Some(joined) = running.join_next().await else {
+            break;
+        };
+        if let Ok(result) = joined
+            && let Some(report) = state.link_audits.reports.lock().await.get_mut(&profile)
+        {
+            report.results.push(result);
+        }
+    }
+
+    if let Some(report) = state.link_audits.reports.lock().await.get_mut(&profile) {
+        report.finished_at = Some(Utc::now());
+    }
+    state.events.publish(
+        EventKind::Status,
+        Some(&profile),
+        json!({ "link_audit": "finished" }),
+    );
+}
+
+/// 检查链接并分类，会跟随重定向找到最终地址
+async fn check_link(client: &reqwest::Client, url: &str) -> LinkStatus {
+    let mut current = url.to_string();
+    let mut first_redirect = None;
+    let mut permanent = true;
+
+    for _ in 0..=MAX_REDIRECTS {
+        let response = match client.get(&current).send().await {
+            Ok(response) => response,
+            Err(err) => return classify_error(&err),
+        };
+        let status = response.status();
+
+        if status.is_redirection()
+            && let Some(location) = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|location| Url::parse(&current).ok()?.join(location).ok())
+        {
+            first_redirect.get_or_insert(status.as_u16());
+            permanent &= matches!(
+                status,
+                StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+            );
+            current = location.to_string();
+            continue;
+        }
+
+        return match first_redirect {
+            Some(first) if status.is_success() => LinkStatus::Redirect {
+                status: first,
+                target: current,
+                permanent,
+            },
+            _ if status.is_client_error() => LinkStatus::ClientError {
+                status: status.as_u16(),
+            },
+            _ if status.is_server_error() => LinkStatus::ServerError {
+                status: status.as_u16(),
+            },
+            _ => LinkStatus::Ok {
+                status: status.as_u16(),
+            },
+        };
+    }
+
+    LinkStatus::ConnectionError {
+        error: "too many redirects".to_string(),
+    }
+}
+
+fn classify_error(err: &reqwest::Error) -> LinkStatus {
+    if err.is_timeout() {
+        return LinkStatus::Timeout;
+    }
+
+    // reqwest 没有单独的 DNS 错误类型，只能检查错误链
+    let mut source = err.source();
+    while let Some(inner) = source {
+        let message = inner.to_string();
+        if message.contains("dns error") || message.contains("failed to lookup address") {
+            return LinkStatus::DnsFailure { error: message };
+        }
+        source = inner.source();
+    }
+
+    LinkStatus::ConnectionError {
+        error: err.to_string(),
+    }
+}
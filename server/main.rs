@@ -1,11 +1,33 @@
 mod api;
+mod avatar;
+mod background;
 mod bookmark;
 mod config;
 mod env;
 pub mod error;
+mod events;
+mod favicon;
+mod gc;
+mod health;
+mod history;
+mod icon;
+mod icon_library;
+mod keyword;
+mod links;
 mod middleware;
+mod network;
+mod rewrite;
+mod rotation;
+mod search;
+mod search_history;
 mod session;
 mod state;
+mod suggest;
+mod trash;
+mod undo;
+mod upload;
+mod usage;
+mod xml;
 
 use anyhow::Result;
 use axum::extract::DefaultBodyLimit;
@@ -36,8 +58,12 @@ async fn main() -> Result<()> {
         .init();
 
     let app_state = AppState::init().await?;
+    upload::cleanup_tmp_dir().await;
+    let upload_config = app_state.storage.lock().await.config.upload.clone();
+    // 留出 multipart 编码的余量，实际大小在接收时检查
+    let body_limit = |max_bytes: u64| DefaultBodyLimit::max(max_bytes as usize + 1024 * 1024);
 
-    let protected_routes = Router::new()
+    let mut protected_routes = Router::new()
         .route(
             "/api/profile",
             get(api::get_profile)
@@ -46,36 +72,90 @@ async fn main() -> Result<()> {
                 .put(update_profile),
         )
         .route("/api/profile/rename", post(api::rename_profile))
+        .route("/api/profile/history", get(api::get_profile_history))
+        .route("/api/profile/restore", post(api::restore_profile))
+        .route("/api/undo", get(api::get_undo_state).post(api::undo))
+        .route("/api/redo", post(api::redo))
         .route("/api/profile/names", get(api::get_all_profile_names))
         .route("/api/profile/sort", post(api::sort_profiles))
+        .route(
+            "/api/profile/rewrite-preview",
+            post(api::preview_url_rewrites),
+        )
         .route(
             "/api/images/icon/{id}",
-            post(api::set_icon)
-                // 增加图标上传大小限制到 10MB
-                .layer(DefaultBodyLimit::max(10 * 1024 * 1024)),
+            post(api::set_icon).layer(body_limit(upload_config.max_icon_bytes)),
         )
+        .route("/api/images/generated/{id}", get(api::get_generated_icon))
+        .route("/api/images/icon/{id}/library", post(api::set_library_icon))
+        .route("/api/images/icon/{id}/status", get(api::get_icon_status))
+        .route("/api/images/icon/{id}/fetch", post(api::refetch_icon))
         .route("/api/fetch-favicon", get(api::fetch_favicon))
+        .route("/api/icon-library", get(api::search_icon_library))
+        .route("/api/icon-library/reindex", post(api::reindex_icon_library))
         .route(
             "/api/background-image",
             post(api::upload_background_image)
                 .get(api::get_background_images)
-                // 增加上传大小限制到 50MB
-                .layer(DefaultBodyLimit::max(50 * 1024 * 1024)),
+                .layer(body_limit(upload_config.max_background_bytes)),
         )
         .route(
             "/api/background-image/delete",
             delete(api::delete_background_image),
         )
+        .route(
+            "/api/background-image/attach",
+            post(api::attach_background_image),
+        )
+        .route(
+            "/api/background-image/current",
+            get(api::get_current_background_image),
+        )
+        .route("/api/background-library", get(api::get_background_library))
+        .route("/api/admin/gc", post(api::collect_garbage))
+        .route("/api/trash", get(api::get_trash).delete(api::empty_trash))
+        .route("/api/trash/{id}", delete(api::purge_trash_entry))
+        .route("/api/trash/{id}/restore", post(api::restore_trash_entry))
+        .route("/api/status", get(api::get_status))
+        .route("/api/events", get(api::events))
+        .route(
+            "/api/maintenance/links",
+            get(api::get_link_report).post(api::start_link_audit),
+        )
+        .route(
+            "/api/maintenance/links/actions",
+            post(api::apply_link_action),
+        )
+        // 搜索和建议会用到书签，浏览器需要已经登录
+        .route("/api/search/redirect", get(api::search_redirect))
+        .route("/api/search/suggestions", get(api::search_suggestions))
+        .route("/api/search/suggest", get(api::search_suggest))
+        .route(
+            "/api/search/history",
+            get(api::get_search_history).delete(api::clear_search_history),
+        )
+        .route(
+            "/api/search/history/{id}",
+            delete(api::delete_search_history_entry),
+        )
+        .route("/go/{target}", get(api::go))
+        .route("/go/{target}/{*rest}", get(api::go))
         // For icons and background images.
-        .nest_service("/images", ServeDir::new(ASSETS_DIR))
-        .layer(axum_middleware::from_fn_with_state(
-            app_state.clone(),
-            auth_middleware,
-        ));
+        .nest_service("/images", ServeDir::new(ASSETS_DIR));
+    // For icons in the local icon library.
+    if let Some(dir) = app_state.icon_library.dir() {
+        protected_routes = protected_routes.nest_service("/icon-library", ServeDir::new(dir));
+    }
+    let protected_routes = protected_routes.layer(axum_middleware::from_fn_with_state(
+        app_state.clone(),
+        auth_middleware,
+    ));
 
     let public_routes = Router::new()
         .route("/api/login", post(api::login))
-        .route("/api/logout", post(api::logout));
+        .route("/api/logout", post(api::logout))
+        // 只包含搜索地址，浏览器添加搜索引擎时通常不带 cookie
+        .route("/opensearch.xml", get(api::opensearch));
 
     let app = Router::new()
         .merge(public_routes)
@@ -107,13 +187,27 @@ async fn main() -> Result<()> {
         .with_state(app_state.clone());
 
     tokio::spawn(tick_save(app_state.clone()));
+    tokio::spawn(icon::icon_worker(app_state.clone()));
+    tokio::spawn(icon::tick_refresh_icons(app_state.clone()));
+    tokio::spawn(gc::tick_gc(app_state.clone()));
+    tokio::spawn(background::backfill_backgrounds(app_state.clone()));
+    tokio::spawn(health::tick_health_checks(app_state.clone()));
+    tokio::spawn(health::tick_certificate_checks(app_state.clone()));
+    tokio::spawn(trash::tick_purge_trash(app_state.clone()));
+    tokio::spawn(search_history::tick_expire_search_history(
+        app_state.clone(),
+    ));
 
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", *PORT)).await?;
     tracing::info!("🚀 Server running on http://0.0.0.0:{}", *PORT);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal(app_state))
-        .await?;
+    // 内网检测需要客户端地址
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(app_state))
+    .await?;
 
     Ok(())
 }
@@ -122,8 +216,7 @@ async fn tick_save(state: AppState) {
     loop {
         tokio::time::sleep(std::time::Duration::from_secs(5 * 60)).await;
         let storage = state.storage.lock().await;
-        let _ = storage.save_sessions().await;
-        let _ = storage.save_profiles().await;
+        let _ = storage.save_all().await;
     }
 }
 
@@ -149,14 +242,12 @@ async fn shutdown_signal(state: AppState) {
         _ = ctrl_c => {
             tracing::info!("Ctrl+C received, shutting down...");
             let storage = state.storage.lock().await;
-            let _ = storage.save_sessions().await;
-            let _ = storage.save_profiles().await;
+            let _ = storage.save_all().await;
         },
         _ = terminate => {
             tracing::info!("Terminate signal received, shutting down...");
             let storage = state.storage.lock().await;
-            let _ = storage.save_sessions().await;
-            let _ = storage.save_profiles().await;
+            let _ = storage.save_all().await;
         },
     }
 }
@@ -0,0 +1,224 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::HeaderMap;
+use axum::http::header::{HOST, USER_AGENT};
+use axum::http::request::Parts;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+
+use crate::bookmark::BookmarkProfile;
+use crate::state::AppState;
+
+/// The zone whose links are stored in `Bookmark::intranet_url`.
+pub const INTRANET_ZONE: &str = "intranet";
+
+/// Rules for telling network zones apart on the server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkRules {
+    /// Client addresses in these networks are in the intranet, e.g. `192.168.1.0/24`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub intranet_cidrs: Vec<IpNet>,
+    /// Requests to these host names are in the intranet, e.g. `nav.lan`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub intranet_hosts: Vec<String>,
+    /// Further access paths such as a VPN, checked in order after the intranet.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub zones: Vec<ZoneRule>,
+    /// Reverse proxies whose `X-Forwarded-For` and `X-Forwarded-Host` headers are trusted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+impl NetworkRules {
+    /// All zones in order, the `intranet_*` fields form the first one.
+    pub fn zone_rules(&self) -> Vec<ZoneRule> {
+        let mut rules = vec![];
+        if !self.intranet_cidrs.is_empty() || !self.intranet_hosts.is_empty() {
+            rules.push(ZoneRule {
+                name: INTRANET_ZONE.to_string(),
+                cidrs: self.intranet_cidrs.clone(),
+                hosts: self.intranet_hosts.clone(),
+            });
+        }
+        rules.extend(self.zones.iter().cloned());
+        rules
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Trusts the proxies of every profile, for requests that don't belong to a profile.
+    pub fn proxies_of(profiles: &[BookmarkProfile]) -> Self {
+        Self {
+            trusted_proxies: profiles
+                .iter()
+                .filter_map(|p| p.network_rules.as_ref())
+                .flat_map(|rules| rules.trusted_proxies.iter().copied())
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A named network zone, e.g. `tailscale` for clients in `100.64.0.0/10`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneRule {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cidrs: Vec<IpNet>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+}
+
+impl ZoneRule {
+    fn matches(&self, ip: IpAddr, host: Option<&str>) -> bool {
+        self.cidrs.iter().any(|net| net.contains(&ip))
+            || host.is_some_and(|host| self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host)))
+    }
+}
+
+/// The request as seen by the network rules.
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub peer: SocketAddr,
+    pub headers: HeaderMap,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr)
+            .unwrap_or_else(|| SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)));
+        Ok(Self {
+            peer,
+            headers: parts.headers.clone(),
+        })
+    }
+}
+
+impl ClientInfo {
+    /// 客户端地址，只信任受信代理添加的 `X-Forwarded-For`
+    pub fn client_ip(&self, rules: &NetworkRules) -> IpAddr {
+        let mut ip = self.peer.ip().to_canonical();
+        if !rules.is_trusted(ip) {
+            return ip;
+        }
+
+        // 从右往左跳过受信代理，第一个不受信的地址就是客户端
+        let forwarded = self
+            .headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|addr| addr.trim().parse::<IpAddr>().ok())
+            .collect::<Vec<_>>();
+        for addr in forwarded.into_iter().rev() {
+            ip = addr.to_canonical();
+            if !rules.is_trusted(ip) {
+                break;
+            }
+        }
+        ip
+    }
+
+    /// 请求的主机名，不含端口
+    fn host(&self, rules: &NetworkRules) -> Option<String> {
+        let forwarded = if rules.is_trusted(self.peer.ip().to_canonical()) {
+            self.headers.get("x-forwarded-host")
+        } else {
+            None
+        };
+        let host = forwarded
+            .or_else(|| self.headers.get(HOST))?
+            .to_str()
+            .ok()?;
+        let host = host.split(',').next()?.trim();
+        let host = match host.strip_prefix('[') {
+            // IPv6 literal, e.g. `[::1]:8080`
+            Some(rest) => rest.split(']').next()?,
+            None => host.split(':').next()?,
+        };
+        Some(host.to_ascii_lowercase())
+    }
+
+    /// 客户端访问的地址，如 `https://nav.example.com:8443`，用于生成绝对链接
+    pub fn origin(&self, rules: &NetworkRules) -> Option<String> {
+        let trusted = rules.is_trusted(self.peer.ip().to_canonical());
+        let header = |name: &str| {
+            let value = self.headers.get(name)?.to_str().ok()?;
+            let value = value.split(',').next()?.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        let forwarded = |name: &str| if trusted { header(name) } else { None };
+        let host = forwarded("x-forwarded-host").or_else(|| header(HOST.as_str()))?;
+        let scheme = forwarded("x-forwarded-proto").unwrap_or_else(|| "http".to_string());
+        Some(format!("{scheme}://{host}"))
+    }
+}
+
+/// The device a request comes from, told apart by the client address and user agent.
+#[derive(Debug, Clone)]
+pub struct Device {
+    /// Resolved through the trusted proxies of all profiles.
+    pub address: IpAddr,
+    pub user_agent: Option<String>,
+}
+
+impl FromRequestParts<AppState> for Device {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let client = ClientInfo::from_request_parts(parts, state).await?;
+        let rules = NetworkRules::proxies_of(&state.storage.lock().await.profiles);
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(Self {
+            address: client.client_ip(&rules),
+            user_agent,
+        })
+    }
+}
+
+impl Device {
+    /// 用于区分设备的标识：客户端地址和 User-Agent
+    pub fn key(&self) -> String {
+        format!(
+            "{} {}",
+            self.address,
+            self.user_agent.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// 根据网络规则找出请求所在的所有区域，按优先级排列，没有配置规则时返回 `None`
+pub fn resolve_zones(rules: &NetworkRules, client: &ClientInfo) -> Option<Vec<String>> {
+    let zone_rules = rules.zone_rules();
+    if zone_rules.is_empty() {
+        return None;
+    }
+
+    let ip = client.client_ip(rules);
+    let host = client.host(rules);
+    Some(
+        zone_rules
+            .into_iter()
+            .filter(|zone| zone.matches(ip, host.as_deref()))
+            .map(|zone| zone.name)
+            .collect(),
+    )
+}
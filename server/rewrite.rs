@@ -0,0 +1,187 @@
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::bookmark::Bookmark;
+use crate::error::{Error, Result};
+use crate::network::INTRANET_ZONE;
+
+/// Derives the link of a network zone from the public `url` of a bookmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewriteRule {
+    /// The zone of the derived link.
+    #[serde(default = "default_zone")]
+    pub zone: String,
+    #[serde(flatten)]
+    pub action: RewriteAction,
+}
+
+fn default_zone() -> String {
+    INTRANET_ZONE.to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RewriteAction {
+    /// Replace the end of the host, e.g. `.example.com` with `.lan`.
+    HostSuffix { from: String, to: String },
+    /// Replace the whole URL, `$1` or `${name}` refer to capture groups.
+    Regex {
+        pattern: String,
+        replacement: String,
+    },
+    /// Send a host to another address, e.g. `jellyfin.example.com` to `192.168.1.10:8096`.
+    PortMap {
+        host: String,
+        target: String,
+        /// Also change the scheme, e.g. to `http` for services without TLS in the LAN.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scheme: Option<String>,
+    },
+}
+
+enum CompiledAction {
+    HostSuffix {
+        from: String,
+        to: String,
+    },
+    Regex {
+        regex: Regex,
+        replacement: String,
+    },
+    PortMap {
+        host: String,
+        target_host: String,
+        target_port: Option<u16>,
+        scheme: Option<String>,
+    },
+}
+
+/// The rewrite rules of a profile, validated and ready to apply.
+pub struct Rewriter {
+    rules: Vec<(String, CompiledAction)>,
+}
+
+impl Rewriter {
+    pub fn new(rules: &[RewriteRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| Ok((rule.zone.clone(), compile(&rule.action)?)))
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Links derived by the first matching rule of each zone, whether or not the bookmark
+    /// has its own link for the zone.
+    pub fn derive(&self, bookmark: &Bookmark) -> IndexMap<String, String> {
+        let mut derived = IndexMap::new();
+        for (zone, action) in &self.rules {
+            if derived.contains_key(zone) {
+                continue;
+            }
+            if let Some(url) = apply(action, &bookmark.url)
+                && url != bookmark.url
+            {
+                derived.insert(zone.clone(), url);
+            }
+        }
+        derived
+    }
+
+    /// 为书签生成各区域的链接，书签自己设置的链接优先
+    pub fn derived_urls(&self, bookmark: &Bookmark) -> IndexMap<String, String> {
+        let mut derived = self.derive(bookmark);
+        derived.retain(|zone, _| bookmark.zone_url(zone).is_none());
+        derived
+    }
+
+    /// The link of the bookmark for a zone, its own link or a derived one.
+    pub fn url_for(&self, bookmark: &Bookmark, zone: &str) -> Option<String> {
+        match bookmark.zone_url(zone) {
+            Some(url) => Some(url.to_string()),
+            None => self.derive(bookmark).shift_remove(zone),
+        }
+    }
+}
+
+fn compile(action: &RewriteAction) -> Result<CompiledAction> {
+    let invalid = |msg: String| Error::InvalidRewriteRule(msg);
+    Ok(match action {
+        RewriteAction::HostSuffix { from, to } => {
+            if from.is_empty() {
+                return Err(invalid("host suffix must not be empty".to_string()));
+            }
+            CompiledAction::HostSuffix {
+                from: from.to_ascii_lowercase(),
+                to: to.clone(),
+            }
+        }
+        RewriteAction::Regex {
+            pattern,
+            replacement,
+        } => CompiledAction::Regex {
+            regex: Regex::new(pattern).map_err(|err| invalid(err.to_string()))?,
+            replacement: replacement.clone(),
+        },
+        RewriteAction::PortMap {
+            host,
+            target,
+            scheme,
+        } => {
+            // 不用 Url 解析端口，它会省略 80 这样的默认端口
+            let (target_host, target_port) = match target.rsplit_once(':') {
+                Some((target_host, port))
+                    if !target_host.contains(':') || target_host.ends_with(']') =>
+                {
+                    let port = port
+                        .parse::<u16>()
+                        .map_err(|err| invalid(format!("{target}: {err}")))?;
+                    (target_host, Some(port))
+                }
+                _ => (target.as_str(), None),
+            };
+            if let Err(err) = Url::parse(&format!("http://{target_host}/")) {
+                return Err(invalid(format!("{target}: {err}")));
+            }
+            CompiledAction::PortMap {
+                host: host.to_ascii_lowercase(),
+                target_host: target_host.to_string(),
+                target_port,
+                scheme: scheme.clone(),
+            }
+        }
+    })
+}
+
+fn apply(action: &CompiledAction, url: &str) -> Option<String> {
+    match action {
+        CompiledAction::HostSuffix { from, to } => {
+            let mut url = Url::parse(url).ok()?;
+            let host = url.host_str()?.to_ascii_lowercase();
+            let prefix = host.strip_suffix(from.as_str())?;
+            url.set_host(Some(&format!("{prefix}{to}"))).ok()?;
+            Some(url.to_string())
+        }
+        CompiledAction::Regex { regex, replacement } => regex
+            .is_match(url)
+            .then(|| regex.replace(url, replacement.as_str()).into_owned()),
+        CompiledAction::PortMap {
+            host,
+            target_host,
+            target_port,
+            scheme,
+        } => {
+            let mut url = Url::parse(url).ok()?;
+            if !url.host_str()?.eq_ignore_ascii_case(host) {
+                return None;
+            }
+            if let Some(scheme) = scheme {
+                url.set_scheme(scheme).ok()?;
+            }
+            url.set_host(Some(target_host)).ok()?;
+            url.set_port(*target_port).ok()?;
+            Some(url.to_string())
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::bookmark::{BackgroundImage, BookmarkProfile, ImageOrientation};
+
+/// Server-side background selection, so every device shows the same image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackgroundRotation {
+    /// Pick a new image every N minutes.
+    pub interval_minutes: u32,
+    /// Offset of the local time used by the rules, e.g. 480 for UTC+8.
+    pub utc_offset_minutes: i32,
+    /// The first matching rule decides which images are used.
+    /// Without a matching rule all images of the profile are used.
+    pub rules: Vec<BackgroundRule>,
+    /// Relative weight per image, 1 if missing.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub weights: HashMap<Uuid, u32>,
+}
+
+impl Default for BackgroundRotation {
+    fn default() -> Self {
+        Self {
+            interval_minutes: 60,
+            utc_offset_minutes: 0,
+            rules: vec![],
+            weights: HashMap::new(),
+        }
+    }
+}
+
+/// A set of images for a time window, e.g. night wallpapers from 19:00 to 07:00.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub images: Vec<Uuid>,
+    /// Local start time, the window may wrap past midnight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<NaiveTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<NaiveTime>,
+    /// Empty means every day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+}
+
+impl BackgroundRule {
+    fn matches(&self, now: DateTime<FixedOffset>) -> bool {
+        if !self.days.is_empty() && !self.days.contains(&now.weekday()) {
+            return false;
+        }
+
+        let time = now.time();
+        match (self.start, self.end) {
+            (Some(start), Some(end)) if start <= end => start <= time && time < end,
+            (Some(start), Some(end)) => time >= start || time < end,
+            (Some(start), None) => time >= start,
+            (None, Some(end)) => time < end,
+            (None, None) => true,
+        }
+    }
+}
+
+/// 当前应显示的背景图
+pub struct CurrentBackground<'a> {
+    pub image: Option<&'a BackgroundImage>,
+    pub next_change_at: DateTime<Utc>,
+}
+
+/// 根据规则确定当前的背景图，相同时间段内结果固定
+pub fn current_background<'a>(
+    profile: &'a BookmarkProfile,
+    orientation: Option<&ImageOrientation>,
+    now: DateTime<Utc>,
+) -> CurrentBackground<'a> {
+    let default = BackgroundRotation::default();
+    let rotation = profile.background_rotation.as_ref().unwrap_or(&default);
+    let interval = rotation.interval_minutes.max(1) as i64 * 60;
+    let slot = now.timestamp().div_euclid(interval);
+    let next_change_at = DateTime::from_timestamp((slot + 1) * interval, 0).unwrap_or(now);
+
+    let offset = FixedOffset::east_opt(rotation.utc_offset_minutes * 60)
+        .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    let local = now.with_timezone(&offset);
+
+    let mut candidates: Vec<&BackgroundImage> =
+        match rotation.rules.iter().find(|r| r.matches(local)) {
+            Some(rule) => profile
+                .background_images
+                .iter()
+                .filter(|bg| rule.images.contains(&bg.id))
+                .collect(),
+            None => vec![],
+        };
+    if candidates.is_empty() {
+        candidates = profile.background_images.iter().collect();
+    }
+
+    // 方图横竖屏都适用，没有匹配方向的图片时使用全部候选
+    if let Some(orientation) = orientation {
+        let matching: Vec<&BackgroundImage> = candidates
+            .iter()
+            .copied()
+            .filter(|bg| {
+                bg.orientation == *orientation || bg.orientation == ImageOrientation::Square
+            })
+            .collect();
+        if !matching.is_empty() {
+            candidates = matching;
+        }
+    }
+
+    let weight = |bg: &BackgroundImage| rotation.weights.get(&bg.id).copied().unwrap_or(1) as u64;
+    let total: u64 = candidates.iter().map(|bg| weight(bg)).sum();
+    if total == 0 {
+        return CurrentBackground {
+            image: None,
+            next_change_at,
+        };
+    }
+
+    let mut pick = splitmix64(slot as u64) % total;
+    let image = candidates.into_iter().find(|bg| {
+        let w = weight(bg);
+        if pick < w {
+            true
+        } else {
+            pick -= w;
+            false
+        }
+    });
+
+    CurrentBackground {
+        image,
+        next_change_at,
+    }
+}
+
+/// Deterministic mixing so consecutive slots pick unrelated images.
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,405 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+use crate::bookmark::{Bookmark, BookmarkProfile};
+use crate::error::{Error, Result};
+use crate::keyword;
+use crate::xml;
+
+/// A web search engine of a profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchEngine {
+    pub name: String,
+    /// Search url with `{}` as the placeholder for the query.
+    pub url: String,
+    /// Bang selecting the engine without the `!`, e.g. `g` for `!g`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<String>,
+    /// Url returning search suggestions, with `{}` as the placeholder for the query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggest_url: Option<String>,
+    /// Used for queries without a bang, the first engine if none is marked.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub default: bool,
+}
+
+impl SearchEngine {
+    fn new(name: &str, url: &str, shortcut: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            shortcut: Some(shortcut.to_string()),
+            suggest_url: None,
+            default: false,
+        }
+    }
+}
+
+pub fn default_engines() -> Vec<SearchEngine> {
+    vec![
+        SearchEngine {
+            default: true,
+            ..SearchEngine::new("Google", "https://www.google.com/search?q={}", "g")
+        },
+        SearchEngine::new("GitHub", "https://github.com/search?q={}", "gh"),
+        SearchEngine::new(
+            "Wikipedia",
+            "https://en.wikipedia.org/w/index.php?search={}",
+            "w",
+        ),
+    ]
+}
+
+/// 兼容旧版本只有一个搜索链接的 `search_engine`
+pub fn deserialize_engines<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Vec<SearchEngine>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Engines {
+        Single(String),
+        List(Vec<SearchEngine>),
+    }
+
+    Ok(match Engines::deserialize(deserializer)? {
+        Engines::List(engines) => engines,
+        Engines::Single(url) if url.is_empty() => vec![],
+        Engines::Single(url) => {
+            let name = Url::parse(&url.replace("{}", ""))
+                .ok()
+                .and_then(|u| {
+                    u.host_str()
+                        .map(|h| h.trim_start_matches("www.").to_string())
+                })
+                .unwrap_or_else(|| "Default".to_string());
+            vec![SearchEngine {
+                name,
+                url,
+                shortcut: None,
+                suggest_url: None,
+                default: true,
+            }]
+        }
+    })
+}
+
+/// 检查搜索引擎的链接和快捷方式，快捷方式在 profile 内不能重复
+pub fn validate_engines(engines: &[SearchEngine]) -> Result<()> {
+    let invalid = |msg: String| Err(Error::InvalidSearchEngine(msg));
+    let mut shortcuts: Vec<&str> = vec![];
+    for engine in engines {
+        if !engine.url.contains("{}") {
+            return invalid(format!("{}: url must contain {{}}", engine.name));
+        }
+        if let Some(suggest_url) = &engine.suggest_url
+            && !suggest_url.contains("{}")
+        {
+            return invalid(format!("{}: suggestion url must contain {{}}", engine.name));
+        }
+        if let Some(shortcut) = &engine.shortcut {
+            if shortcut.is_empty() || shortcut.chars().any(|c| c.is_whitespace() || c == '!') {
+                return invalid(format!("{}: invalid shortcut", engine.name));
+            }
+            if shortcuts.iter().any(|s| s.eq_ignore_ascii_case(shortcut)) {
+                return invalid(format!(
+                    "{}: shortcut !{shortcut} is already used",
+                    engine.name
+                ));
+            }
+            shortcuts.push(shortcut);
+        }
+    }
+    if engines.iter().filter(|e| e.default).count() > 1 {
+        return invalid("only one engine can be the default".to_string());
+    }
+    Ok(())
+}
+
+pub fn default_engine(engines: &[SearchEngine]) -> Option<&SearchEngine> {
+    engines.iter().find(|e| e.default).or(engines.first())
+}
+
+/// 按名称或快捷方式查找搜索引擎，不区分大小写
+pub fn find_engine<'a>(engines: &'a [SearchEngine], name: &str) -> Option<&'a SearchEngine> {
+    let name = name.trim_start_matches('!');
+    engines.iter().find(|e| {
+        e.name.eq_ignore_ascii_case(name)
+            || e.shortcut
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Where a search query leads.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchTarget {
+    pub url: String,
+    /// The search engine used, `None` for a bookmark search.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// The bookmark whose `search_url` is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bookmark: Option<Uuid>,
+    /// The query without the bang or keyword.
+    pub terms: String,
+}
+
+impl SearchTarget {
+    /// Whether the bookmark itself is opened instead of searched.
+    pub fn opens_bookmark(&self) -> bool {
+        self.bookmark.is_some() && self.terms.is_empty()
+    }
+}
+
+/// 和 `encodeURIComponent` 相同的编码
+pub fn encode(terms: &str) -> String {
+    url::form_urlencoded::byte_serialize(terms.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+/// 代入搜索词，没有搜索词时打开搜索引擎的首页
+fn fill(template: &str, terms: &str) -> String {
+    if terms.is_empty()
+        && let Ok(url) = Url::parse(&template.replace("{}", ""))
+    {
+        return format!("{}/", url.origin().ascii_serialization());
+    }
+    template.replace("{}", &encode(terms))
+}
+
+/// 解析搜索内容：
+/// - `!g rust` 或 `rust !g` 使用快捷方式对应的搜索引擎，也可以是带 `search_url` 的书签关键字
+/// - `gh tokio` 以书签关键字开头时使用该书签的 `search_url`
+/// - 只有书签关键字，或和 profile 中书签的标题相同时打开该书签
+/// - 其他内容使用默认搜索引擎
+pub fn resolve(
+    profiles: &[BookmarkProfile],
+    profile: &BookmarkProfile,
+    query: &str,
+) -> Option<SearchTarget> {
+    let query = query.trim();
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let bang = match (words.first(), words.last()) {
+        (Some(first), _) if first.len() > 1 && first.starts_with('!') => {
+            Some((&first[1..], words[1..].join(" ")))
+        }
+        (_, Some(last)) if last.len() > 1 && last.starts_with('!') => {
+            Some((&last[1..], words[..words.len() - 1].join(" ")))
+        }
+        _ => None,
+    };
+    if let Some((bang, terms)) = bang {
+        let engine = profile.search_engines.iter().find(|e| {
+            e.shortcut
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(bang))
+        });
+        if let Some(engine) = engine {
+            return Some(SearchTarget {
+                url: fill(&engine.url, &terms),
+                engine: Some(engine.name.clone()),
+                bookmark: None,
+                terms,
+            });
+        }
+        if let Some(target) = bookmark_search(profiles, bang, terms) {
+            return Some(target);
+        }
+    }
+
+    if let [first, rest @ ..] = words.as_slice()
+        && !rest.is_empty()
+        && let Some(target) = bookmark_search(profiles, first, rest.join(" "))
+    {
+        return Some(target);
+    }
+
+    if let Some(bookmark) = exact_bookmark(profiles, profile, query) {
+        return Some(SearchTarget {
+            url: bookmark.url.clone(),
+            engine: None,
+            bookmark: Some(bookmark.id),
+            terms: String::new(),
+        });
+    }
+
+    let engine = default_engine(&profile.search_engines)?;
+    Some(SearchTarget {
+        url: fill(&engine.url, query),
+        engine: Some(engine.name.clone()),
+        bookmark: None,
+        terms: query.to_string(),
+    })
+}
+
+fn bookmark_search(
+    profiles: &[BookmarkProfile],
+    keyword: &str,
+    terms: String,
+) -> Option<SearchTarget> {
+    // 书签 ID 不算关键字
+    if Uuid::parse_str(keyword).is_ok() {
+        return None;
+    }
+    let (_, bookmark) = keyword::find(profiles, keyword)?;
+    let search_url = bookmark.search_url.as_deref()?;
+    let url = if terms.is_empty() {
+        bookmark.url.clone()
+    } else {
+        search_url.replace("{}", &encode(&terms))
+    };
+    Some(SearchTarget {
+        url,
+        engine: None,
+        bookmark: Some(bookmark.id),
+        terms,
+    })
+}
+
+fn exact_bookmark<'a>(
+    profiles: &'a [BookmarkProfile],
+    profile: &'a BookmarkProfile,
+    query: &str,
+) -> Option<&'a Bookmark> {
+    if let Some((_, bookmark)) = keyword::find(profiles, query)
+        && bookmark.keyword.is_some()
+    {
+        return Some(bookmark);
+    }
+    profile.bookmarks.iter().find(|b| {
+        b.title.eq_ignore_ascii_case(query)
+            || b.search_title
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(query))
+    })
+}
+
+/// A completion offered while typing.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// Text that resolves to the same target when searched.
+    pub completion: String,
+    pub description: String,
+    /// The bookmark opened by the completion.
+    pub bookmark: Option<Uuid>,
+}
+
+/// 根据书签的关键字和标题补全，`!` 开头时补全搜索引擎的快捷方式。前缀匹配优先，其次按 `frecency` 排序
+pub fn suggest(
+    profile: &BookmarkProfile,
+    query: &str,
+    frecency: impl Fn(Uuid) -> f64,
+    limit: usize,
+) -> Vec<Suggestion> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+
+    if let Some(bang) = query.strip_prefix('!')
+        && !bang.contains(char::is_whitespace)
+    {
+        return profile
+            .search_engines
+            .iter()
+            .filter_map(|engine| {
+                let shortcut = engine.shortcut.as_deref()?;
+                shortcut
+                    .to_lowercase()
+                    .starts_with(bang)
+                    .then(|| Suggestion {
+                        completion: format!("!{shortcut} "),
+                        description: engine.name.clone(),
+                        bookmark: None,
+                    })
+            })
+            .take(limit)
+            .collect();
+    }
+
+    let mut matches: Vec<(u8, f64, &Bookmark)> = profile
+        .bookmarks
+        .iter()
+        .filter_map(|bookmark| {
+            let keyword = bookmark.keyword.as_deref().map(str::to_lowercase);
+            let titles = [Some(&bookmark.title), bookmark.search_title.as_ref()]
+                .into_iter()
+                .flatten()
+                .map(|t| t.to_lowercase())
+                .collect::<Vec<_>>();
+            let rank = if keyword.as_deref().is_some_and(|k| k.starts_with(&query)) {
+                0
+            } else if titles.iter().any(|t| t.starts_with(&query)) {
+                1
+            } else if titles.iter().any(|t| t.contains(&query)) {
+                2
+            } else {
+                return None;
+            };
+            Some((rank, frecency(bookmark.id), bookmark))
+        })
+        .collect();
+    matches.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)));
+
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, bookmark)| Suggestion {
+            completion: bookmark
+                .keyword
+                .clone()
+                .unwrap_or_else(|| bookmark.title.clone()),
+            description: bookmark.title.clone(),
+            bookmark: Some(bookmark.id),
+        })
+        .collect()
+}
+
+/// 生成 OpenSearch 描述文件，浏览器的搜索和建议都经过服务端解析
+pub fn opensearch_description(origin: &str, profile: &BookmarkProfile) -> String {
+    let params = format!("profile={}", encode(&profile.name));
+    let engines = profile
+        .search_engines
+        .iter()
+        .map(|engine| match &engine.shortcut {
+            Some(shortcut) => format!("{} (!{shortcut})", engine.name),
+            None => engine.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    // ShortName 最多 16 个字符
+    let short_name: String = format!("anav {}", profile.name).chars().take(16).collect();
+    let description = if engines.is_empty() {
+        format!("Bookmarks of {}", profile.name)
+    } else {
+        format!("Bookmarks of {}, {engines}", profile.name)
+    };
+    let search = xml::escape(&format!(
+        "{origin}/api/search/redirect?q={{searchTerms}}&{params}"
+    ));
+    let suggestions = xml::escape(&format!(
+        "{origin}/api/search/suggestions?q={{searchTerms}}&{params}"
+    ));
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/" xmlns:moz="http://www.mozilla.org/2006/browser/search/">
+  <ShortName>{short_name}</ShortName>
+  <Description>{description}</Description>
+  <InputEncoding>UTF-8</InputEncoding>
+  <Image width="32" height="32" type="image/webp">{origin}/compass.webp</Image>
+  <Url type="text/html" method="get" template="{search}"/>
+  <Url type="application/x-suggestions+json" method="get" template="{suggestions}"/>
+  <moz:SearchForm>{origin}/</moz:SearchForm>
+</OpenSearchDescription>
+"#,
+        short_name = xml::escape(&short_name),
+        description = xml::escape(&description),
+        origin = xml::escape(origin),
+    )
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::SearchHistoryConfig;
+use crate::network::Device;
+use crate::state::AppState;
+
+const SEARCH_HISTORY_PATH: &str = "./config/search_history.json";
+
+/// Whose search history a request belongs to: the user when logged in, otherwise the device.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchOwner(String);
+
+impl FromRequestParts<AppState> for SearchOwner {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        // 只有一个账号，登录后所有设备共用历史；不保存会话 ID，历史记录不能用来登录
+        if CookieJar::from_headers(&parts.headers)
+            .get("session_id")
+            .is_some()
+        {
+            return Ok(Self("user".to_string()));
+        }
+        let device = Device::from_request_parts(parts, state).await?;
+        Ok(Self(device.key()))
+    }
+}
+
+/// A search submitted from the search box or the browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchEntry {
+    pub id: Uuid,
+    pub at: DateTime<Utc>,
+    pub query: String,
+    pub profile: String,
+    /// The search engine used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// The bookmark opened or searched in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bookmark: Option<Uuid>,
+}
+
+impl SearchEntry {
+    pub fn new(
+        query: &str,
+        profile: String,
+        engine: Option<String>,
+        bookmark: Option<Uuid>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            query: query.trim().to_string(),
+            profile,
+            engine,
+            bookmark,
+        }
+    }
+}
+
+/// Searches of each owner, newest last.
+#[derive(Default)]
+pub struct SearchHistory {
+    owners: HashMap<String, Vec<SearchEntry>>,
+}
+
+impl Debug for SearchHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "search history of {} owners", self.owners.len())
+    }
+}
+
+impl SearchHistory {
+    pub fn load() -> Result<Self> {
+        let owners = if Path::new(SEARCH_HISTORY_PATH).exists() {
+            serde_json::from_str(&fs::read_to_string(SEARCH_HISTORY_PATH)?)?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self { owners })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(SEARCH_HISTORY_PATH, serde_json::to_string(&self.owners)?)?;
+        Ok(())
+    }
+
+    /// 记录一次搜索，超过数量上限时删除最早的记录
+    pub fn record(
+        &mut self,
+        config: &SearchHistoryConfig,
+        owner: &SearchOwner,
+        entry: SearchEntry,
+    ) {
+        if entry.query.is_empty() {
+            return;
+        }
+        let entries = self.owners.entry(owner.0.clone()).or_default();
+        entries.push(entry);
+        if config.max_entries > 0 && entries.len() > config.max_entries {
+            let excess = entries.len() - config.max_entries;
+            entries.drain(..excess);
+        }
+    }
+
+    /// Entries of the owner, newest first.
+    pub fn entries(&self, owner: &SearchOwner) -> impl Iterator<Item = &SearchEntry> {
+        self.owners
+            .get(&owner.0)
+            .into_iter()
+            .flat_map(|entries| entries.iter().rev())
+    }
+
+    pub fn remove(&mut self, owner: &SearchOwner, id: Uuid) -> bool {
+        let Some(entries) = self.owners.get_mut(&owner.0) else {
+            return false;
+        };
+        let len = entries.len();
+        entries.retain(|entry| entry.id != id);
+        let removed = entries.len() < len;
+        if entries.is_empty() {
+            self.owners.remove(&owner.0);
+        }
+        removed
+    }
+
+    /// 清空该用户的搜索历史，返回删除的条目数量
+    pub fn clear(&mut self, owner: &SearchOwner) -> usize {
+        self.owners
+            .remove(&owner.0)
+            .map_or(0, |entries| entries.len())
+    }
+
+    /// Recent searches starting with the query, newest first and without duplicates.
+    pub fn recent_queries(&self, owner: &SearchOwner, query: &str) -> Vec<String> {
+        let query = query.trim().to_lowercase();
+        let mut queries: Vec<String> = vec![];
+        for entry in self.entries(owner) {
+            let lower = entry.query.to_lowercase();
+            if lower.starts_with(&query)
+                && lower.len() > query.len()
+                && !queries.iter().any(|q| q.to_lowercase() == lower)
+            {
+                queries.push(entry.query.clone());
+            }
+        }
+        queries
+    }
+
+    /// How often each bookmark was chosen by the owner's searches.
+    pub fn bookmark_choices(&self, owner: &SearchOwner) -> HashMap<Uuid, u64> {
+        let mut choices = HashMap::new();
+        for id in self.entries(owner).filter_map(|entry| entry.bookmark) {
+            *choices.entry(id).or_default() += 1;
+        }
+        choices
+    }
+
+    /// 删除超过保留期限的条目，返回删除的条目数量
+    fn expire(&mut self, retention_days: u64) -> usize {
+        let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+        let mut expired = 0;
+        self.owners.retain(|_, entries| {
+            let len = entries.len();
+            entries.retain(|entry| entry.at >= cutoff);
+            expired += len - entries.len();
+            !entries.is_empty()
+        });
+        expired
+    }
+
+    fn clear_all(&mut self) -> usize {
+        self.owners.drain().map(|(_, entries)| entries.len()).sum()
+    }
+}
+
+/// 定期删除过期的搜索历史，关闭搜索历史时删除所有记录
+pub async fn tick_expire_search_history(state: AppState) {
+    loop {
+        {
+            let mut storage = state.storage.lock().await;
+            let config = storage.config.search_history.clone();
+            let removed = if !config.enabled {
+                storage.search_history.clear_all()
+            } else if config.retention_days > 0 {
+                storage.search_history.expire(config.retention_days)
+            } else {
+                0
+            };
+            if removed > 0 {
+                tracing::info!("Removed {removed} search history entries");
+                if let Err(err) = storage.search_history.save() {
+                    tracing::warn!("Failed to save search history: {err}");
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+    }
+}
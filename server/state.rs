@@ -4,20 +4,50 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use tokio::sync::Mutex;
+use uuid::Uuid;
 
-use crate::bookmark::BookmarkProfile;
+use crate::bookmark::{BackgroundImage, Bookmark, BookmarkProfile};
 use crate::config::Config;
+use crate::events::EventBus;
+use crate::health::HealthMonitor;
+use crate::history::{Actor, History, HistoryAction};
+use crate::icon::IconJobs;
+use crate::icon_library::IconLibrary;
+use crate::links::LinkAudits;
+use crate::search_history::SearchHistory;
 use crate::session::SessionStore;
+use crate::suggest::SearchSuggestions;
+use crate::trash::{TrashEntry, TrashItem};
+use crate::undo::UndoLog;
+use crate::usage::UsageStats;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub storage: Arc<Mutex<Storage>>,
+    pub icon_jobs: IconJobs,
+    pub icon_library: IconLibrary,
+    pub health: HealthMonitor,
+    pub link_audits: LinkAudits,
+    pub events: EventBus,
+    pub undo: UndoLog,
+    pub suggestions: SearchSuggestions,
 }
 
 impl AppState {
     pub async fn init() -> Result<Self> {
         let storage = Storage::init().await?;
-        Ok(Self { storage })
+        let icon_library =
+            IconLibrary::new(storage.lock().await.config.icon.library_dir.as_deref());
+        Ok(Self {
+            storage,
+            icon_jobs: IconJobs::default(),
+            icon_library,
+            health: HealthMonitor::default(),
+            link_audits: LinkAudits::default(),
+            events: EventBus::default(),
+            undo: UndoLog::default(),
+            suggestions: SearchSuggestions::default(),
+        })
     }
 }
 
@@ -26,12 +56,21 @@ pub struct Storage {
     pub config: Config,
     pub profiles: Vec<BookmarkProfile>,
     pub sessions: SessionStore,
+    /// Instance-wide background image library, profiles reference its images by id.
+    pub images: Vec<BackgroundImage>,
+    pub history: History,
+    /// Deleted items, their asset files are kept until they are purged.
+    pub trash: Vec<TrashEntry>,
+    pub usage: UsageStats,
+    pub search_history: SearchHistory,
 }
 
 const CONFIG_DIR: &str = "./config";
 const CONFIG_PATH: &str = "./config/config.json";
 const PROFILES_PATH: &str = "./config/profiles.json";
 const SESSIONS_PATH: &str = "./config/sessions.json";
+const IMAGES_PATH: &str = "./config/images.json";
+const TRASH_PATH: &str = "./config/trash.json";
 
 impl Storage {
     async fn ensure_config_file() -> Result<()> {
@@ -71,12 +110,38 @@ impl Storage {
         let mut sessions: SessionStore = serde_json::from_str(&fs::read_to_string(SESSIONS_PATH)?)?;
         sessions.cleanup_sessions();
 
-        let storage = Arc::new(Mutex::new(Self {
+        // Before the library existed, images were only stored in the profiles.
+        let images = if Path::new(IMAGES_PATH).exists() {
+            serde_json::from_str(&fs::read_to_string(IMAGES_PATH)?)?
+        } else {
+            let mut images: Vec<BackgroundImage> = Vec::new();
+            for bg in profiles.iter().flat_map(|p| &p.background_images) {
+                if !images.iter().any(|i| i.id == bg.id) {
+                    images.push(bg.clone());
+                }
+            }
+            fs::write(IMAGES_PATH, serde_json::to_string_pretty(&images)?)?;
+            images
+        };
+
+        let trash = if Path::new(TRASH_PATH).exists() {
+            serde_json::from_str(&fs::read_to_string(TRASH_PATH)?)?
+        } else {
+            vec![]
+        };
+
+        let mut storage = Self {
             profiles,
             config,
             sessions,
-        }));
-        Ok(storage)
+            images,
+            history: History::load()?,
+            trash,
+            usage: UsageStats::load()?,
+            search_history: SearchHistory::load()?,
+        };
+        storage.sync_background_images();
+        Ok(Arc::new(Mutex::new(storage)))
     }
 
     pub async fn save_sessions(&self) -> Result<()> {
@@ -89,6 +154,118 @@ impl Storage {
         Ok(())
     }
 
+    pub async fn save_images(&self) -> Result<()> {
+        fs::write(IMAGES_PATH, serde_json::to_string_pretty(&self.images)?)?;
+        Ok(())
+    }
+
+    pub async fn save_trash(&self) -> Result<()> {
+        fs::write(TRASH_PATH, serde_json::to_string_pretty(&self.trash)?)?;
+        Ok(())
+    }
+
+    pub async fn save_all(&self) -> Result<()> {
+        self.save_sessions().await?;
+        self.save_profiles().await?;
+        self.save_images().await?;
+        self.save_trash().await?;
+        self.usage.save()?;
+        self.search_history.save()?;
+        Ok(())
+    }
+
+    /// Refresh the background images of every profile from the library.
+    ///
+    /// Profiles keep a copy of each image record for the client, the library is the source of
+    /// truth. Images missing from the library are dropped.
+    pub fn sync_background_images(&mut self) {
+        for profile in &mut self.profiles {
+            let mut synced: Vec<BackgroundImage> = Vec::new();
+            for bg in &profile.background_images {
+                if let Some(image) = self.images.iter().find(|i| i.id == bg.id)
+                    && !synced.iter().any(|i| i.id == bg.id)
+                {
+                    synced.push(image.clone());
+                }
+            }
+            profile.background_images = synced;
+        }
+    }
+
+    /// Remove library images that no profile uses any more and return them.
+    ///
+    /// Images of profiles in the trash are still in use, restoring the profile needs them.
+    pub fn prune_images(&mut self) -> Vec<BackgroundImage> {
+        let (used, unused) = std::mem::take(&mut self.images)
+            .into_iter()
+            .partition(|image| {
+                self.profiles
+                    .iter()
+                    .chain(self.trash.iter().filter_map(TrashEntry::profile))
+                    .any(|p| p.background_images.iter().any(|bg| bg.id == image.id))
+            });
+        self.images = used;
+        unused
+    }
+
+    /// Move the bookmarks removed from the profile since `before` to the trash.
+    pub fn trash_removed_bookmarks(&mut self, before: &BookmarkProfile) {
+        let Some(profile) = self.profiles.iter().find(|p| p.name == before.name) else {
+            return;
+        };
+        for (index, bookmark) in before.bookmarks.iter().enumerate() {
+            if !profile.bookmarks.iter().any(|b| b.id == bookmark.id) {
+                self.trash.push(TrashEntry::new(TrashItem::Bookmark {
+                    profile: before.name.clone(),
+                    bookmark: bookmark.clone(),
+                    index,
+                }));
+            }
+        }
+    }
+
+    /// Drop trashed bookmarks that are back in the profile.
+    pub fn untrash_bookmarks(&mut self, name: &str) {
+        let Some(profile) = self.profiles.iter().find(|p| p.name == name) else {
+            return;
+        };
+        self.trash.retain(|entry| match &entry.item {
+            TrashItem::Bookmark {
+                profile: trashed_from,
+                bookmark,
+                ..
+            } => trashed_from != name || !profile.bookmarks.iter().any(|b| b.id == bookmark.id),
+            _ => true,
+        });
+    }
+
+    /// Move library images no profile uses any more to the trash.
+    pub fn trash_unused_images(&mut self, profile: &str) {
+        for image in self.prune_images() {
+            self.trash.push(TrashEntry::new(TrashItem::BackgroundImage {
+                image,
+                profiles: vec![profile.to_string()],
+            }));
+        }
+    }
+
+    /// Record a mutation of a profile, `before` is a copy taken before the change.
+    pub fn record_history(
+        &mut self,
+        actor: Actor,
+        action: HistoryAction,
+        before: Option<&BookmarkProfile>,
+        after: Option<&str>,
+    ) {
+        let after = after.and_then(|name| self.profiles.iter().find(|p| p.name == name));
+        if let Err(err) = self
+            .history
+            .record(&self.config.history, actor, action, before, after)
+        {
+            tracing::warn!("Failed to record history: {err}");
+        }
+    }
+
     pub fn get_profile(&self, name: &str) -> Option<&BookmarkProfile> {
         self.profiles.iter().find(|p| p.name == name)
     }
@@ -97,6 +274,28 @@ impl Storage {
         self.profiles.iter_mut().find(|p| p.name == name)
     }
 
+    pub fn get_bookmark(&self, id: Uuid) -> Option<&Bookmark> {
+        self.profiles
+            .iter()
+            .flat_map(|p| &p.bookmarks)
+            .find(|b| b.id == id)
+    }
+
+    /// The name of the profile containing the bookmark.
+    pub fn bookmark_profile(&self, id: Uuid) -> Option<&str> {
+        self.profiles
+            .iter()
+            .find(|p| p.bookmarks.iter().any(|b| b.id == id))
+            .map(|p| p.name.as_str())
+    }
+
+    pub fn get_bookmark_mut(&mut self, id: Uuid) -> Option<&mut Bookmark> {
+        self.profiles
+            .iter_mut()
+            .flat_map(|p| &mut p.bookmarks)
+            .find(|b| b.id == id)
+    }
+
     pub async fn get_default_profile(&mut self) -> &BookmarkProfile {
         if self.profiles.is_empty() {
             self.profiles.push(BookmarkProfile::default());
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::favicon;
+use crate::search;
+
+/// How long suggestions of an engine are reused.
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+const MAX_CACHED: usize = 512;
+/// Suggestions are only useful while typing, give up quickly.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+const MAX_RESPONSE_BYTES: usize = 256 * 1024;
+
+/// An entry of the search box suggestions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SuggestItem {
+    Bookmark {
+        text: String,
+        title: String,
+        id: Uuid,
+    },
+    /// A recent search from the search history.
+    Recent { text: String },
+    /// Suggested by the search engine.
+    Engine { text: String },
+}
+
+impl SuggestItem {
+    pub fn text(&self) -> &str {
+        match self {
+            SuggestItem::Bookmark { text, .. }
+            | SuggestItem::Recent { text }
+            | SuggestItem::Engine { text } => text,
+        }
+    }
+}
+
+/// Suggestions by request url.
+type SuggestCache = HashMap<String, (Instant, Vec<String>)>;
+
+/// Proxies the suggestion APIs of search engines, which browsers can't call because of CORS.
+#[derive(Clone)]
+pub struct SearchSuggestions {
+    client: reqwest::Client,
+    cache: Arc<Mutex<SuggestCache>>,
+}
+
+impl std::fmt::Debug for SearchSuggestions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SearchSuggestions")
+    }
+}
+
+impl Default for SearchSuggestions {
+    fn default() -> Self {
+        // 和获取 favicon 使用相同的请求设置
+        let client = favicon::http_client_builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            cache: Arc::default(),
+        }
+    }
+}
+
+impl SearchSuggestions {
+    /// 获取搜索引擎的建议，失败时返回空列表
+    pub async fn engine_suggestions(&self, template: &str, query: &str) -> Vec<String> {
+        let url = template.replace("{}", &search::encode(query));
+        {
+            let cache = self.cache.lock().await;
+            if let Some((at, suggestions)) = cache.get(&url)
+                && at.elapsed() < CACHE_TTL
+            {
+                return suggestions.clone();
+            }
+        }
+
+        let suggestions = match self.fetch(&url).await {
+            Ok(suggestions) => suggestions,
+            Err(err) => {
+                tracing::debug!("Failed to fetch search suggestions from {url}: {err}");
+                return vec![];
+            }
+        };
+
+        let mut cache = self.cache.lock().await;
+        if cache.len() >= MAX_CACHED {
+            cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+            if cache.len() >= MAX_CACHED {
+                cache.clear();
+            }
+        }
+        cache.insert(url, (Instant::now(), suggestions.clone()));
+        suggestions
+    }
+
+    async fn fetch(&self, url: &str) -> anyhow::Result<Vec<String>> {
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        let bytes = favicon::read_body(response, MAX_RESPONSE_BYTES).await?;
+        Ok(parse_suggestions(&serde_json::from_slice(&bytes)?))
+    }
+}
+
+/// 解析 OpenSearch 格式 `["query", ["suggestion", ...]]`，也支持 `[{"phrase": "..."}]`
+fn parse_suggestions(value: &Value) -> Vec<String> {
+    let items = match value {
+        Value::Array(array) => match array.get(1) {
+            Some(Value::Array(items)) if array.first().is_some_and(Value::is_string) => items,
+            _ => array,
+        },
+        _ => return vec![],
+    };
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Value::String(text) => Some(text.clone()),
+            Value::Object(object) => object
+                .get("phrase")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::background::remove_background_files;
+use crate::bookmark::{BackgroundImage, Bookmark, BookmarkProfile};
+use crate::state::{AppState, Storage};
+
+/// Something deleted, kept until it is restored or purged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TrashItem {
+    Bookmark {
+        profile: String,
+        bookmark: Bookmark,
+        /// Position in the profile when it was deleted.
+        index: usize,
+    },
+    /// The profile with all its bookmarks.
+    Profile {
+        profile: BookmarkProfile,
+        index: usize,
+    },
+    /// A library image no profile used any more.
+    BackgroundImage {
+        image: BackgroundImage,
+        /// Profiles that used it before the deletion.
+        profiles: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: Uuid,
+    pub deleted_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub item: TrashItem,
+}
+
+impl TrashEntry {
+    pub fn new(item: TrashItem) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            deleted_at: Utc::now(),
+            item,
+        }
+    }
+
+    pub fn bookmarks(&self) -> impl Iterator<Item = &Bookmark> {
+        let bookmarks: &[Bookmark] = match &self.item {
+            TrashItem::Bookmark { bookmark, .. } => std::slice::from_ref(bookmark),
+            TrashItem::Profile { profile, .. } => &profile.bookmarks,
+            TrashItem::BackgroundImage { .. } => &[],
+        };
+        bookmarks.iter()
+    }
+
+    /// The deleted profile if the entry is one.
+    pub fn profile(&self) -> Option<&BookmarkProfile> {
+        match &self.item {
+            TrashItem::Profile { profile, .. } => Some(profile),
+            _ => None,
+        }
+    }
+
+    pub fn background_images(&self) -> impl Iterator<Item = &BackgroundImage> {
+        let images: &[BackgroundImage] = match &self.item {
+            TrashItem::Bookmark { .. } => &[],
+            TrashItem::Profile { profile, .. } => &profile.background_images,
+            TrashItem::BackgroundImage { image, .. } => std::slice::from_ref(image),
+        };
+        images.iter()
+    }
+}
+
+/// 删除回收站中的条目，不再被引用的背景图文件直接删除，图标文件由 GC 清理
+pub async fn purge(storage: &mut Storage, ids: &[Uuid]) -> usize {
+    let (purged, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut storage.trash)
+        .into_iter()
+        .partition(|entry| ids.contains(&entry.id));
+    storage.trash = kept;
+
+    // 只有已删除的 profile 还在使用的背景图随 profile 一起删除
+    let pruned = storage.prune_images();
+    if !pruned.is_empty()
+        && let Err(err) = storage.save_images().await
+    {
+        tracing::warn!("Failed to save background images: {err}");
+    }
+
+    for image in purged
+        .iter()
+        .flat_map(|entry| entry.background_images())
+        .chain(&pruned)
+    {
+        let in_use = storage.images.iter().any(|i| i.id == image.id)
+            || storage
+                .trash
+                .iter()
+                .flat_map(|entry| entry.background_images())
+                .any(|i| i.id == image.id);
+        if !in_use {
+            remove_background_files(image).await;
+        }
+    }
+
+    // 彻底删除的书签不再需要使用统计
+    if purged
+        .iter()
+        .any(|entry| entry.bookmarks().next().is_some())
+    {
+        let ids: HashSet<Uuid> = storage
+            .profiles
+            .iter()
+            .flat_map(|p| &p.bookmarks)
+            .chain(storage.trash.iter().flat_map(|entry| entry.bookmarks()))
+            .map(|b| b.id)
+            .collect();
+        storage.usage.retain(&ids);
+        if let Err(err) = storage.usage.save() {
+            tracing::warn!("Failed to save usage stats: {err}");
+        }
+    }
+    purged.len()
+}
+
+/// 定期清理回收站中过期的条目
+pub async fn tick_purge_trash(state: AppState) {
+    loop {
+        {
+            let mut storage = state.storage.lock().await;
+            let retention_days = storage.config.trash.retention_days;
+            if retention_days > 0 {
+                let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+                let expired: Vec<Uuid> = storage
+                    .trash
+                    .iter()
+                    .filter(|entry| entry.deleted_at < cutoff)
+                    .map(|entry| entry.id)
+                    .collect();
+                if !expired.is_empty() {
+                    let purged = purge(&mut storage, &expired).await;
+                    tracing::info!("Purged {purged} expired trash entries");
+                    let _ = storage.save_trash().await;
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+    }
+}
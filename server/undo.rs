@@ -0,0 +1,393 @@
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum_extra::extract::CookieJar;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::bookmark::{BookmarkProfile, IconSource};
+use crate::error::{Error, Result};
+use crate::keyword;
+use crate::network::Device;
+use crate::state::{AppState, Storage};
+use crate::trash::{TrashEntry, TrashItem};
+
+/// Sessions whose operations are kept, the least recently used ones are dropped first.
+const MAX_SESSIONS: usize = 32;
+
+/// The operation log an undo applies to: the login session, or the device when login is off.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UndoSession(String);
+
+impl FromRequestParts<AppState> for UndoSession {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        if let Some(cookie) = CookieJar::from_headers(&parts.headers).get("session_id") {
+            return Ok(Self(cookie.value().to_string()));
+        }
+        let device = Device::from_request_parts(parts, state).await?;
+        Ok(Self(device.key()))
+    }
+}
+
+/// A mutation and enough state to apply it in both directions.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Create {
+        profile: BookmarkProfile,
+        index: usize,
+        /// The trash entry made by undoing the creation.
+        trash_id: Option<Uuid>,
+    },
+    /// Bookmarks or settings of a profile changed.
+    Update {
+        before: Box<BookmarkProfile>,
+        after: Box<BookmarkProfile>,
+    },
+    Delete {
+        profile: BookmarkProfile,
+        index: usize,
+        /// The trash entry made by the deletion.
+        trash_id: Option<Uuid>,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    Sort {
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Create,
+    Update,
+    Delete,
+    Rename,
+    Sort,
+}
+
+impl Operation {
+    pub fn kind(&self) -> OperationKind {
+        match self {
+            Operation::Create { .. } => OperationKind::Create,
+            Operation::Update { .. } => OperationKind::Update,
+            Operation::Delete { .. } => OperationKind::Delete,
+            Operation::Rename { .. } => OperationKind::Rename,
+            Operation::Sort { .. } => OperationKind::Sort,
+        }
+    }
+
+    /// The profile changed by the operation, `None` for sorting.
+    pub fn profile(&self) -> Option<&str> {
+        match self {
+            Operation::Create { profile, .. } | Operation::Delete { profile, .. } => {
+                Some(&profile.name)
+            }
+            Operation::Update { after, .. } => Some(&after.name),
+            Operation::Rename { to, .. } => Some(to),
+            Operation::Sort { .. } => None,
+        }
+    }
+
+    /// The old and new name if applying the operation renames a profile.
+    fn renamed(&self, forward: bool) -> Option<(&str, &str)> {
+        match self {
+            Operation::Rename { from, to } if forward => Some((from, to)),
+            Operation::Rename { from, to } => Some((to, from)),
+            _ => None,
+        }
+    }
+
+    /// 撤销 (`forward = false`) 或重做操作，当前数据和操作后的状态不一致时拒绝
+    pub fn apply(&mut self, storage: &mut Storage, forward: bool) -> Result<()> {
+        match self {
+            Operation::Create {
+                profile,
+                index,
+                trash_id,
+            } => {
+                if forward {
+                    insert_profile(storage, profile, *index, *trash_id)?;
+                } else {
+                    *trash_id = Some(remove_profile(storage, profile)?);
+                }
+            }
+            Operation::Delete {
+                profile,
+                index,
+                trash_id,
+            } => {
+                if forward {
+                    *trash_id = Some(remove_profile(storage, profile)?);
+                } else {
+                    insert_profile(storage, profile, *index, *trash_id)?;
+                }
+            }
+            Operation::Update { before, after } => {
+                let (from, to) = if forward {
+                    (&**before, &**after)
+                } else {
+                    (&**after, &**before)
+                };
+                keyword::check_keywords(&storage.profiles, to)?;
+                let Some(profile) = storage.get_profile_mut(&from.name) else {
+                    return Err(Error::UndoConflict);
+                };
+                if !same_profile(profile, from) {
+                    return Err(Error::UndoConflict);
+                }
+                *profile = to.clone();
+                storage.sync_background_images();
+                storage.trash_removed_bookmarks(from);
+                storage.untrash_bookmarks(&to.name);
+                storage.trash_unused_images(&to.name);
+            }
+            Operation::Rename { from, to } => {
+                let (from, to) = if forward {
+                    (&*from, &*to)
+                } else {
+                    (&*to, &*from)
+                };
+                if storage.get_profile(to).is_some() {
+                    return Err(Error::UndoConflict);
+                }
+                if storage.get_profile(from).is_none() {
+                    return Err(Error::UndoConflict);
+                }
+                // 先修改可能失败的历史记录，失败时 profile 保持原名
+                storage.history.rename_profile(from, to)?;
+                if let Some(profile) = storage.get_profile_mut(from) {
+                    profile.name = to.clone();
+                }
+            }
+            Operation::Sort { before, after } => {
+                let (from, to) = if forward {
+                    (&*before, &*after)
+                } else {
+                    (&*after, &*before)
+                };
+                let current: Vec<&str> = storage.profiles.iter().map(|p| p.name.as_str()).collect();
+                if current != from.iter().map(String::as_str).collect::<Vec<_>>() {
+                    return Err(Error::UndoConflict);
+                }
+                storage
+                    .profiles
+                    .sort_by_key(|p| to.iter().position(|name| *name == p.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn insert_profile(
+    storage: &mut Storage,
+    profile: &BookmarkProfile,
+    index: usize,
+    trash_id: Option<Uuid>,
+) -> Result<()> {
+    if storage.get_profile(&profile.name).is_some() {
+        return Err(Error::UndoConflict);
+    }
+    keyword::check_keywords(&storage.profiles, profile)?;
+    if let Some(trash_id) = trash_id {
+        storage.trash.retain(|entry| entry.id != trash_id);
+    }
+    // 背景图可能已经从图库中移除
+    for bg in &profile.background_images {
+        if !storage.images.iter().any(|i| i.id == bg.id) {
+            storage.images.push(bg.clone());
+        }
+    }
+    let index = index.min(storage.profiles.len());
+    storage.profiles.insert(index, profile.clone());
+    storage.sync_background_images();
+    Ok(())
+}
+
+/// 移除 profile 并放入回收站，返回回收站条目的 id
+fn remove_profile(storage: &mut Storage, profile: &BookmarkProfile) -> Result<Uuid> {
+    let Some(index) = storage.profiles.iter().position(|p| p.name == profile.name) else {
+        return Err(Error::UndoConflict);
+    };
+    if !same_profile(&storage.profiles[index], profile) {
+        return Err(Error::UndoConflict);
+    }
+    if storage.profiles.len() <= 1 {
+        return Err(Error::CannotDeleteLastProfile);
+    }
+    let removed = storage.profiles.remove(index);
+    let entry = TrashEntry::new(TrashItem::Profile {
+        profile: removed,
+        index,
+    });
+    let id = entry.id;
+    storage.trash.push(entry);
+    storage.prune_images();
+    Ok(id)
+}
+
+/// 比较时忽略后台自动获取的图标，它们不算其他人的修改；手动设置的图标仍然比较
+pub fn same_profile(current: &BookmarkProfile, expected: &BookmarkProfile) -> bool {
+    let mut expected = expected.clone();
+    for bookmark in &mut expected.bookmarks {
+        if let Some(current) = current.bookmarks.iter().find(|b| b.id == bookmark.id)
+            && current.icon_source == IconSource::Fetched
+        {
+            bookmark.icon = current.icon.clone();
+            bookmark.icon_source = IconSource::Fetched;
+        }
+    }
+    serde_json::to_value(current).ok() == serde_json::to_value(&expected).ok()
+}
+
+#[derive(Debug)]
+struct SessionOperations {
+    undo: VecDeque<Operation>,
+    redo: Vec<Operation>,
+    last_used: Instant,
+}
+
+impl Default for SessionOperations {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: vec![],
+            last_used: Instant::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct UndoState {
+    /// Operations that can be undone.
+    pub undo: usize,
+    /// Undone operations that can be redone.
+    pub redo: usize,
+}
+
+/// The last operations of each session, kept in memory.
+#[derive(Debug, Clone, Default)]
+pub struct UndoLog {
+    sessions: Arc<Mutex<HashMap<UndoSession, SessionOperations>>>,
+}
+
+impl UndoLog {
+    /// 记录新的操作，之前撤销的操作不能再重做
+    pub async fn push(&self, session: UndoSession, operation: Operation, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+        let mut sessions = self.sessions.lock().await;
+        if !sessions.contains_key(&session)
+            && sessions.len() >= MAX_SESSIONS
+            && let Some(oldest) = sessions
+                .iter()
+                .min_by_key(|(_, operations)| operations.last_used)
+                .map(|(session, _)| session.clone())
+        {
+            sessions.remove(&oldest);
+        }
+        let operations = sessions.entry(session).or_default();
+        operations.last_used = Instant::now();
+        operations.redo.clear();
+        operations.undo.push_back(operation);
+        while operations.undo.len() > depth {
+            operations.undo.pop_front();
+        }
+    }
+
+    pub async fn state(&self, session: &UndoSession) -> UndoState {
+        let sessions = self.sessions.lock().await;
+        sessions
+            .get(session)
+            .map(|operations| UndoState {
+                undo: operations.undo.len(),
+                redo: operations.redo.len(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// 删除会话的操作记录，例如登出时
+    pub async fn forget(&self, session: &UndoSession) {
+        self.sessions.lock().await.remove(session);
+    }
+
+    /// 撤销或重做最近的操作并保存，失败时数据和操作都恢复原状
+    pub async fn step(
+        &self,
+        session: &UndoSession,
+        storage: &mut Storage,
+        forward: bool,
+    ) -> Result<(Operation, UndoState)> {
+        let mut sessions = self.sessions.lock().await;
+        let Some(operations) = sessions.get_mut(session) else {
+            return Err(Error::NothingToUndo);
+        };
+        operations.last_used = Instant::now();
+        let taken = if forward {
+            operations.redo.pop()
+        } else {
+            operations.undo.pop_back()
+        };
+        let Some(mut operation) = taken else {
+            return Err(Error::NothingToUndo);
+        };
+
+        let snapshot = (
+            storage.profiles.clone(),
+            storage.images.clone(),
+            storage.trash.clone(),
+        );
+        let result = match operation.apply(storage, forward) {
+            Ok(()) => save(storage).await.map_err(|err| {
+                // 保存失败时恢复内存中的数据，改名时历史记录也要改回来
+                (storage.profiles, storage.images, storage.trash) = snapshot;
+                if let Some((from, to)) = operation.renamed(forward)
+                    && let Err(err) = storage.history.rename_profile(to, from)
+                {
+                    tracing::warn!("Failed to restore history: {err}");
+                }
+                Error::from(err)
+            }),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            if forward {
+                operations.redo.push(operation);
+            } else {
+                operations.undo.push_back(operation);
+            }
+            return Err(err);
+        }
+        if forward {
+            operations.undo.push_back(operation.clone());
+        } else {
+            operations.redo.push(operation.clone());
+        }
+        let state = UndoState {
+            undo: operations.undo.len(),
+            redo: operations.redo.len(),
+        };
+        Ok((operation, state))
+    }
+}
+
+async fn save(storage: &Storage) -> anyhow::Result<()> {
+    storage.save_profiles().await?;
+    storage.save_images().await?;
+    storage.save_trash().await?;
+    Ok(())
+}
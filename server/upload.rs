@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use axum::extract::Multipart;
+use image::ImageFormat;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use crate::api::{BACKGROUND_DIR, ICON_DIR};
+use crate::config::UploadConfig;
+use crate::error::{Error, Result};
+
+/// Uploads are streamed here first, on the same file system as the assets so they can be renamed.
+pub const UPLOAD_TMP_DIR: &str = "./config/tmp";
+
+/// Bytes kept from the start of the upload to detect the format.
+const HEAD_LEN: usize = 64;
+
+/// An uploaded file in `UPLOAD_TMP_DIR`, removed on drop unless persisted.
+#[derive(Debug)]
+pub struct TempUpload {
+    path: PathBuf,
+    pub filename: Option<String>,
+    pub size: u64,
+    /// SHA-256 of the content as hex.
+    pub hash: String,
+    head: Vec<u8>,
+    persisted: bool,
+}
+
+impl TempUpload {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Image format from the file extension, or from the content.
+    pub fn format(&self) -> Option<ImageFormat> {
+        self.filename
+            .as_deref()
+            .and_then(|name| name.split('.').next_back())
+            .and_then(ImageFormat::from_extension)
+            .or_else(|| image::guess_format(&self.head).ok())
+    }
+
+    /// Move the file to its final place, which is atomic on the same file system.
+    pub fn persist(mut self, target: impl AsRef<Path>) -> Result<()> {
+        std::fs::rename(&self.path, target)?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for TempUpload {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 将 multipart 中的文件流式写入临时目录，同时计算哈希
+pub async fn receive_file(
+    multipart: &mut Multipart,
+    field_name: &str,
+    max_bytes: u64,
+) -> Result<TempUpload> {
+    tokio::fs::create_dir_all(UPLOAD_TMP_DIR).await?;
+
+    while let Some(mut field) = multipart.next_field().await? {
+        if field.name() != Some(field_name) {
+            continue;
+        }
+
+        let mut upload = TempUpload {
+            path: PathBuf::from(format!("{UPLOAD_TMP_DIR}/{}.part", Uuid::new_v4())),
+            filename: field.file_name().map(str::to_string),
+            size: 0,
+            hash: String::new(),
+            head: Vec::new(),
+            persisted: false,
+        };
+        let mut file = tokio::fs::File::create(&upload.path).await?;
+        let mut hasher = Sha256::new();
+
+        while let Some(chunk) = field.chunk().await? {
+            upload.size += chunk.len() as u64;
+            if upload.size > max_bytes {
+                return Err(Error::FileTooLarge);
+            }
+            if upload.head.len() < HEAD_LEN {
+                let take = (HEAD_LEN - upload.head.len()).min(chunk.len());
+                upload.head.extend_from_slice(&chunk[..take]);
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.sync_all().await?;
+
+        upload.hash = format!("{:x}", hasher.finalize());
+        return Ok(upload);
+    }
+
+    Err(Error::BadRequest)
+}
+
+/// 检查图标和背景图占用的总空间是否超过配额
+pub async fn check_quota(config: &UploadConfig, incoming: u64) -> Result<()> {
+    if config.quota_bytes == 0 {
+        return Ok(());
+    }
+
+    let mut used = 0;
+    for dir in [ICON_DIR, BACKGROUND_DIR] {
+        let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+            continue;
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            used += entry.metadata().await?.len();
+        }
+    }
+
+    if used + incoming > config.quota_bytes {
+        return Err(Error::QuotaExceeded);
+    }
+    Ok(())
+}
+
+/// 删除上次运行遗留的临时文件
+pub async fn cleanup_tmp_dir() {
+    let _ = tokio::fs::remove_dir_all(UPLOAD_TMP_DIR).await;
+}
@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const USAGE_PATH: &str = "./config/usage.json";
+
+/// Opens kept per bookmark for the frecency score.
+const RECENT_OPENS: usize = 10;
+
+/// A single open through `/go/{id}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRecord {
+    pub at: DateTime<Utc>,
+    /// The zone whose link was opened, `None` for `url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    pub profile: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BookmarkUsage {
+    count: u64,
+    /// Newest last.
+    recent: VecDeque<OpenRecord>,
+}
+
+/// Aggregated usage of a bookmark, sent with the bookmark and never stored in the profile.
+#[derive(Debug, Clone, Serialize)]
+pub struct BookmarkStats {
+    pub count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_opened: Option<DateTime<Utc>>,
+    /// Higher for bookmarks opened often and recently.
+    pub frecency: f64,
+    /// Times the requesting user chose the bookmark from a search.
+    pub searches: u64,
+}
+
+/// 按最近打开时间加权，越久以前的打开权重越低
+fn recency_weight(at: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    match (now - at).num_days() {
+        ..4 => 100.0,
+        4..14 => 70.0,
+        14..31 => 50.0,
+        31..90 => 30.0,
+        _ => 10.0,
+    }
+}
+
+impl BookmarkUsage {
+    fn stats(&self, now: DateTime<Utc>) -> BookmarkStats {
+        let frecency = if self.recent.is_empty() {
+            0.0
+        } else {
+            let weights: f64 = self.recent.iter().map(|r| recency_weight(r.at, now)).sum();
+            self.count as f64 * weights / self.recent.len() as f64
+        };
+        BookmarkStats {
+            count: self.count,
+            last_opened: self.recent.back().map(|r| r.at),
+            frecency,
+            searches: 0,
+        }
+    }
+}
+
+/// How often each bookmark was opened, stored apart from the profiles so that saving a
+/// profile never overwrites it.
+#[derive(Default)]
+pub struct UsageStats {
+    bookmarks: HashMap<Uuid, BookmarkUsage>,
+}
+
+impl Debug for UsageStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "usage of {} bookmarks", self.bookmarks.len())
+    }
+}
+
+impl UsageStats {
+    pub fn load() -> Result<Self> {
+        let bookmarks = if Path::new(USAGE_PATH).exists() {
+            serde_json::from_str(&fs::read_to_string(USAGE_PATH)?)?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self { bookmarks })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(USAGE_PATH, serde_json::to_string(&self.bookmarks)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, id: Uuid, record: OpenRecord) {
+        let usage = self.bookmarks.entry(id).or_default();
+        usage.count += 1;
+        usage.recent.push_back(record);
+        while usage.recent.len() > RECENT_OPENS {
+            usage.recent.pop_front();
+        }
+    }
+
+    pub fn stats(&self, id: Uuid) -> Option<BookmarkStats> {
+        self.bookmarks.get(&id).map(|usage| usage.stats(Utc::now()))
+    }
+
+    /// 移除已经彻底删除的书签
+    pub fn retain(&mut self, ids: &HashSet<Uuid>) {
+        self.bookmarks.retain(|id, _| ids.contains(id));
+    }
+}
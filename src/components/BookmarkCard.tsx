@@ -3,8 +3,14 @@ import { useTranslation } from "react-i18next";
 import { ExternalLink, Globe, Edit, Trash2, Link } from "lucide-react";
 import { highlightText } from "@/lib/highlight";
 import { cn } from "@/lib/utils";
-import type { Bookmark } from "@/types/bookmark";
-import { iconUrl } from "@/lib/api";
+import type { Bookmark, HealthState } from "@/types/bookmark";
+import { goUrl, iconUrl } from "@/lib/api";
+import {
+  INTRANET_ZONE,
+  bookmarkGoUrl,
+  bookmarkUrl,
+  bookmarkZoneUrls,
+} from "@/lib/bookmark-utils";
 import {
   ContextMenu,
   ContextMenuContent,
@@ -20,13 +26,21 @@ interface BookmarkCardProps {
    */
   searchQuery?: string;
   /**
-   * 是否使用内网链接（如果有）
+   * 当前所在的网络区域（按优先级），决定使用哪个链接
    */
-  useIntranetUrl?: boolean;
+  zones?: string[];
   /**
    * 是否有背景图（有背景时卡片半透明）
    */
   hasBackground?: boolean;
+  /**
+   * 服务端健康检查的结果，未检查时不显示
+   */
+  healthState?: HealthState;
+  /**
+   * 证书即将过期或已过期
+   */
+  certExpiring?: boolean;
   /**
    * 编辑回调
    */
@@ -40,19 +54,20 @@ interface BookmarkCardProps {
 export function BookmarkCard({
   bookmark,
   searchQuery = "",
-  useIntranetUrl = false,
+  zones = [],
   hasBackground = false,
+  healthState,
+  certExpiring = false,
   onEdit,
   onDelete,
 }: BookmarkCardProps) {
   const { t } = useTranslation();
   const [iconError, setIconError] = useState(false);
 
-  // 根据内网状态决定使用哪个链接
-  const displayUrl =
-    useIntranetUrl && bookmark.intranet_url
-      ? bookmark.intranet_url
-      : bookmark.url;
+  // 根据网络区域决定使用哪个链接
+  const displayUrl = bookmarkUrl(bookmark, zones);
+  // 通过 /go 打开，记录使用次数
+  const openUrl = bookmarkGoUrl(bookmark, zones);
 
   const handleEdit = () => {
     onEdit?.(bookmark);
@@ -62,21 +77,30 @@ export function BookmarkCard({
     onDelete?.(bookmark);
   };
 
-  // 获取另一个链接（如果当前显示内网链接则返回外网链接，反之亦然）
-  const alternateUrl =
-    useIntranetUrl && bookmark.intranet_url
-      ? bookmark.url // 当前是内网链接，返回外网链接
-      : bookmark.intranet_url; // 当前是外网链接，返回内网链接（可能为空）
+  // 其他网络区域的链接（不含当前显示的链接），null 表示外网链接
+  const alternateUrls: [string | null, string][] = [
+    ...bookmarkZoneUrls(bookmark),
+    [null, bookmark.url] as [string | null, string],
+  ].filter(([, url]) => url !== displayUrl);
 
-  const handleOpenAlternate = () => {
-    if (alternateUrl) {
-      window.location.href = alternateUrl;
+  const alternateLabel = (zone: string | null) => {
+    if (zone === null) {
+      return t("bookmarkCard.openExtranet");
+    }
+    if (zone === INTRANET_ZONE) {
+      return t("bookmarkCard.openIntranet");
     }
+    return t("bookmarkCard.openZone", { zone });
+  };
+
+  const handleOpenAlternate = (zone: string | null) => {
+    window.location.href = goUrl(bookmark.id, zone);
   };
 
   const cardContent = (
     <a
-      href={displayUrl}
+      href={openUrl}
+      title={displayUrl}
       className={cn(
         "group border rounded-lg p-3",
         "hover:shadow-md transition-all hover:scale-105",
@@ -91,7 +115,7 @@ export function BookmarkCard({
     >
       {bookmark.icon && !iconError ? (
         <img
-          src={iconUrl(bookmark.icon)}
+          src={iconUrl(bookmark.icon, bookmark.id)}
           alt={bookmark.title}
           className="size-7 object-contain rounded-md"
           onError={() => setIconError(true)}
@@ -122,24 +146,46 @@ export function BookmarkCard({
           )}
         </div>
       </div>
+      {healthState && healthState !== "Unknown" && (
+        <span
+          className={cn(
+            "size-2 rounded-full shrink-0",
+            healthState === "Down"
+              ? "bg-red-500"
+              : certExpiring
+                ? "bg-amber-500"
+                : "bg-green-500"
+          )}
+          title={
+            healthState === "Down"
+              ? t("bookmarkCard.serviceDown")
+              : certExpiring
+                ? t("bookmarkCard.certExpiring")
+                : t("bookmarkCard.serviceUp")
+          }
+        />
+      )}
       <ExternalLink className="size-3 text-muted-foreground opacity-0 group-hover:opacity-100 transition-opacity shrink-0" />
     </a>
   );
 
-  if (onEdit || onDelete || alternateUrl) {
+  if (onEdit || onDelete || alternateUrls.length > 0) {
     return (
       <ContextMenu>
         <ContextMenuTrigger asChild>{cardContent}</ContextMenuTrigger>
         <ContextMenuContent>
-          {alternateUrl && (
-            <ContextMenuItem onClick={handleOpenAlternate}>
+          {alternateUrls.map(([zone]) => (
+            <ContextMenuItem
+              key={zone ?? ""}
+              onClick={() => handleOpenAlternate(zone)}
+            >
               <Link className="size-4 mr-2" />
-              {useIntranetUrl && bookmark.intranet_url
-                ? t("bookmarkCard.openExtranet")
-                : t("bookmarkCard.openIntranet")}
+              {alternateLabel(zone)}
             </ContextMenuItem>
+          ))}
+          {alternateUrls.length > 0 && (onEdit || onDelete) && (
+            <ContextMenuSeparator />
           )}
-          {alternateUrl && (onEdit || onDelete) && <ContextMenuSeparator />}
           {onEdit && (
             <ContextMenuItem onClick={handleEdit}>
               <Edit className="size-4 mr-2" />
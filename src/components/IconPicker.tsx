@@ -9,6 +9,10 @@ interface IconPickerProps {
    * 当前图标文件名（如果有）
    */
   currentIcon?: string;
+  /**
+   * 书签 ID（用于显示字母头像）
+   */
+  bookmarkId?: string;
   /**
    * 书签 URL（用于自动获取图标）
    */
@@ -39,6 +43,7 @@ function base64ToFile(base64: string, contentType: string, filename: string): Fi
 
 export function IconPicker({
   currentIcon,
+  bookmarkId,
   bookmarkUrl,
   onIconChange,
   disabled = false,
@@ -58,8 +63,8 @@ export function IconPicker({
 
   // 从 prop 计算默认预览（不触发 re-render）
   const defaultPreview = useMemo(
-    () => (currentIcon ? iconUrl(currentIcon) : null),
-    [currentIcon]
+    () => (currentIcon ? iconUrl(currentIcon, bookmarkId) : null),
+    [currentIcon, bookmarkId]
   );
 
   // 最终显示的预览：优先使用用户选择的，否则使用默认值
@@ -59,6 +59,7 @@ export function NewBookmarkDialog({
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState("");
   const [searchUrlError, setSearchUrlError] = useState("");
+  const [keywordError, setKeywordError] = useState("");
   const [iconFile, setIconFile] = useState<File | null>(null);
 
   const isEditMode = !!bookmark;
@@ -70,6 +71,7 @@ export function NewBookmarkDialog({
   const [tags, setTags] = useState<string[]>(bookmark?.tags || []);
   const [searchTitle, setSearchTitle] = useState(bookmark?.search_title || "");
   const [searchUrl, setSearchUrl] = useState(bookmark?.search_url || "");
+  const [keyword, setKeyword] = useState(bookmark?.keyword || "");
 
   const isControlled = open !== undefined;
   const isOpen = isControlled ? open : internalOpen;
@@ -84,6 +86,7 @@ export function NewBookmarkDialog({
       setTags(bookmark.tags || []);
       setSearchTitle(bookmark.search_title || "");
       setSearchUrl(bookmark.search_url || "");
+      setKeyword(bookmark.keyword || "");
       setIconFile(null); // 编辑模式下重置图标文件
     } else if (isOpen && !isEditMode) {
       // 新建模式：重置表单
@@ -93,6 +96,7 @@ export function NewBookmarkDialog({
       setTags([]);
       setSearchTitle("");
       setSearchUrl("");
+      setKeyword("");
       setIconFile(null);
     }
   }, [isOpen, isEditMode, bookmark]);
@@ -125,6 +129,25 @@ export function NewBookmarkDialog({
       }
       setSearchUrlError("");
 
+      // 关键字在所有 profile 中唯一，重复时由服务端拒绝
+      const keywordTrimmed = keyword.trim();
+      if (keywordTrimmed && !/^[A-Za-z0-9._-]+$/.test(keywordTrimmed)) {
+        setKeywordError(t("newBookmarkDialog.keywordInvalid"));
+        setLoading(false);
+        return;
+      }
+      const keywordTaken = profile.bookmarks.some(
+        (b) =>
+          b.id !== bookmark?.id &&
+          b.keyword?.toLowerCase() === keywordTrimmed.toLowerCase()
+      );
+      if (keywordTrimmed && keywordTaken) {
+        setKeywordError(t("newBookmarkDialog.keywordTaken"));
+        setLoading(false);
+        return;
+      }
+      setKeywordError("");
+
       if (isEditMode && bookmark) {
         // 编辑模式：更新现有书签
         const updatedBookmark: Bookmark = {
@@ -134,6 +157,7 @@ export function NewBookmarkDialog({
           search_title: searchTitle.trim() || undefined,
           intranet_url: intranetUrl.trim() || undefined,
           search_url: searchUrl.trim() || undefined,
+          keyword: keywordTrimmed || undefined,
           tags: tags,
         };
 
@@ -164,6 +188,7 @@ export function NewBookmarkDialog({
           search_title: searchTitle.trim() || undefined,
           intranet_url: intranetUrl.trim() || undefined,
           search_url: searchUrl.trim() || undefined,
+          keyword: keywordTrimmed || undefined,
           tags: tags,
         };
 
@@ -189,6 +214,7 @@ export function NewBookmarkDialog({
         setTags([]);
         setSearchTitle("");
         setSearchUrl("");
+        setKeyword("");
       }
       setIsOpen(false);
       onSuccess?.();
@@ -207,6 +233,7 @@ export function NewBookmarkDialog({
       // 关闭时重置错误
       setError("");
       setSearchUrlError("");
+      setKeywordError("");
     }
   };
 
@@ -317,11 +344,37 @@ export function NewBookmarkDialog({
               {searchUrlError && <FieldError>{searchUrlError}</FieldError>}
             </Field>
 
+            <Field>
+              <FieldLabel htmlFor="keyword">
+                {t("newBookmarkDialog.keyword")}
+              </FieldLabel>
+              <FieldContent>
+                <Input
+                  id="keyword"
+                  value={keyword}
+                  onChange={(e) => {
+                    setKeyword(e.target.value);
+                    setKeywordError("");
+                  }}
+                  placeholder={t("newBookmarkDialog.keywordPlaceholder")}
+                  disabled={loading}
+                />
+              </FieldContent>
+              {keywordError ? (
+                <FieldError>{keywordError}</FieldError>
+              ) : (
+                <p className="text-xs text-muted-foreground">
+                  {t("newBookmarkDialog.keywordHint")}
+                </p>
+              )}
+            </Field>
+
             <Field>
               <FieldLabel>{t("common.icon")}</FieldLabel>
               <FieldContent>
                 <IconPicker
                   currentIcon={bookmark?.icon}
+                  bookmarkId={bookmark?.id}
                   bookmarkUrl={url}
                   onIconChange={setIconFile}
                   disabled={loading}
@@ -8,12 +8,16 @@ import {
 import { useTranslation } from "react-i18next";
 import { Search, Globe, Link2 } from "lucide-react";
 import { Input } from "@/components/ui/input";
-import { SearchDropdown } from "@/components/SearchDropdown";
+import {
+  SearchDropdown,
+  type SearchSuggestion,
+} from "@/components/SearchDropdown";
 import { Kbd } from "@/components/ui/kbd";
 import { useMediaQuery } from "@/hooks/use-media-query";
 import { cn } from "@/lib/utils";
 import type { Bookmark } from "@/types/bookmark";
 import { iconUrl } from "@/lib/api";
+import { bookmarkGoUrl } from "@/lib/bookmark-utils";
 import { detectUrl } from "@/lib/utils";
 
 interface SearchBoxProps {
@@ -25,10 +29,14 @@ interface SearchBoxProps {
   className?: string;
   autoFocus?: boolean;
   searchResults?: Bookmark[];
+  /** 最近的搜索和搜索引擎的建议，显示在书签之后 */
+  suggestions?: SearchSuggestion[];
+  onSelectSuggestion?: (text: string) => void;
   onSelectResult?: (bookmark: Bookmark) => void;
   onSelectedBookmarkChange?: (bookmark: Bookmark | null) => void;
   hasBackground?: boolean;
-  useIntranetUrl?: boolean;
+  /** 当前所在的网络区域（按优先级） */
+  zones?: string[];
 }
 
 export function SearchBox({
@@ -40,10 +48,12 @@ export function SearchBox({
   className,
   autoFocus = true,
   searchResults = [],
+  suggestions = [],
+  onSelectSuggestion,
   onSelectResult,
   onSelectedBookmarkChange,
   hasBackground = false,
-  useIntranetUrl = false,
+  zones = [],
 }: SearchBoxProps) {
   const { t } = useTranslation();
   const inputRef = useRef<HTMLInputElement>(null);
@@ -97,12 +107,19 @@ export function SearchBox({
     };
   }, [isFocused, isDropdownOpen]);
 
+  // 书签在前，建议在后
+  const itemCount = searchResults.length + suggestions.length;
+  const selectedSuggestion =
+    selectedIndex >= searchResults.length
+      ? suggestions[selectedIndex - searchResults.length]
+      : undefined;
+
   // 当搜索结果变化时，重置选中索引和下拉框状态
   // 下拉框显示条件：聚焦 + 有搜索关键词 + 有搜索结果 + 没有选中书签 + 不是 URL 模式
   const shouldShowDropdown =
     isFocused &&
     value.trim().length > 0 &&
-    searchResults.length > 0 &&
+    itemCount > 0 &&
     !selectedBookmark &&
     !isUrlMode;
 
@@ -118,7 +135,7 @@ export function SearchBox({
     }, 0);
 
     return () => clearTimeout(timer);
-  }, [value, searchResults.length, shouldShowDropdown, isFocused]);
+  }, [value, itemCount, shouldShowDropdown, isFocused]);
 
   const handleSubmit = (e: React.FormEvent) => {
     e.preventDefault();
@@ -134,14 +151,11 @@ export function SearchBox({
     onSubmit?.(e);
   };
 
-  // 根据内网状态决定使用哪个链接
+  // 根据网络区域决定使用哪个链接，从搜索结果中打开时带上搜索内容
   const getBookmarkUrl = useCallback(
-    (bookmark: Bookmark): string => {
-      return useIntranetUrl && bookmark.intranet_url
-        ? bookmark.intranet_url
-        : bookmark.url;
-    },
-    [useIntranetUrl]
+    (bookmark: Bookmark, query?: string): string =>
+      bookmarkGoUrl(bookmark, zones, query),
+    [zones]
   );
 
   const handleKeyDown = (e: KeyboardEvent<HTMLInputElement>) => {
@@ -165,24 +179,26 @@ export function SearchBox({
     }
 
     // 如果没有选中书签，处理下拉框的键盘操作
-    if (!isDropdownOpen || searchResults.length === 0) {
+    if (!isDropdownOpen || itemCount === 0) {
       return;
     }
 
     switch (e.key) {
       case "ArrowDown":
         e.preventDefault();
-        setSelectedIndex((prev) =>
-          prev < searchResults.length - 1 ? prev + 1 : 0
-        );
+        setSelectedIndex((prev) => (prev < itemCount - 1 ? prev + 1 : 0));
         break;
       case "ArrowUp":
         e.preventDefault();
-        setSelectedIndex((prev) =>
-          prev > 0 ? prev - 1 : searchResults.length - 1
-        );
+        setSelectedIndex((prev) => (prev > 0 ? prev - 1 : itemCount - 1));
         break;
       case "Tab":
+        if (selectedSuggestion) {
+          // Tab：把建议填入搜索框
+          e.preventDefault();
+          onChange(selectedSuggestion.text);
+          break;
+        }
         if (selectedIndex >= 0 && selectedIndex < searchResults.length) {
           e.preventDefault();
           const selected = searchResults[selectedIndex];
@@ -197,7 +213,7 @@ export function SearchBox({
           } else {
             // 没有 search_url：直接进入网页
             onSelectResult?.(selected);
-            window.location.href = getBookmarkUrl(selected);
+            window.location.href = getBookmarkUrl(selected, value);
             setIsDropdownOpen(false);
           }
         }
@@ -212,7 +228,7 @@ export function SearchBox({
   const handleSelect = (bookmark: Bookmark) => {
     onSelectResult?.(bookmark);
     // 点击直接进入网页
-    window.location.href = getBookmarkUrl(bookmark);
+    window.location.href = getBookmarkUrl(bookmark, value);
     setIsDropdownOpen(false);
     onChange(""); // 清空搜索框
     setSelectedBookmark(null);
@@ -226,7 +242,7 @@ export function SearchBox({
         {selectedBookmark ? (
           selectedBookmark.icon && !iconError ? (
             <img
-              src={iconUrl(selectedBookmark.icon)}
+              src={iconUrl(selectedBookmark.icon, selectedBookmark.id)}
               alt={selectedBookmark.title}
               className="absolute left-4 top-1/2 -translate-y-1/2 size-5 object-contain z-10"
               onError={() => setIconError(true)}
@@ -250,7 +266,7 @@ export function SearchBox({
             setIsFocused(true);
             if (
               value.trim().length > 0 &&
-              searchResults.length > 0 &&
+              itemCount > 0 &&
               !selectedBookmark
             ) {
               setIsDropdownOpen(true);
@@ -292,32 +308,45 @@ export function SearchBox({
                   <Kbd>Enter</Kbd>
                   <span>{t("searchBox.search")}</span>
                 </div>
-                {value.trim().length > 0 && searchResults.length > 0 && (
+                {value.trim().length > 0 && selectedSuggestion && (
                   <div className="flex items-center gap-1.5">
                     <Kbd>Tab</Kbd>
-                    <span>
-                      {searchResults[selectedIndex]?.search_url
-                        ? t("searchBox.useBookmarkSearch", {
-                            title: searchResults[selectedIndex].title,
-                          })
-                        : t("searchBox.openBookmark", {
-                            title: searchResults[selectedIndex].title,
-                          })}
-                    </span>
+                    <span>{t("searchBox.useSuggestion")}</span>
                   </div>
                 )}
+                {value.trim().length > 0 &&
+                  !selectedSuggestion &&
+                  searchResults.length > 0 && (
+                    <div className="flex items-center gap-1.5">
+                      <Kbd>Tab</Kbd>
+                      <span>
+                        {searchResults[selectedIndex]?.search_url
+                          ? t("searchBox.useBookmarkSearch", {
+                              title: searchResults[selectedIndex].title,
+                            })
+                          : t("searchBox.openBookmark", {
+                              title: searchResults[selectedIndex].title,
+                            })}
+                      </span>
+                    </div>
+                  )}
               </>
             )}
           </div>
         )}
         <SearchDropdown
           results={searchResults}
+          suggestions={suggestions}
+          onSelectSuggestion={(text) => {
+            setIsDropdownOpen(false);
+            onSelectSuggestion?.(text);
+          }}
           searchQuery={value}
           isOpen={isDropdownOpen}
           selectedIndex={selectedIndex}
           onSelect={handleSelect}
           onClose={() => setIsDropdownOpen(false)}
-          useIntranetUrl={useIntranetUrl}
+          zones={zones}
         />
       </div>
     </form>
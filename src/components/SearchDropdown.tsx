@@ -1,17 +1,26 @@
 import { useEffect, useRef, useState } from "react";
-import { Globe, ExternalLink, Search } from "lucide-react";
+import { Globe, ExternalLink, History, Search } from "lucide-react";
 import type { Bookmark } from "@/types/bookmark";
 import { highlightText } from "@/lib/highlight";
 import { iconUrl } from "@/lib/api";
+import { bookmarkGoUrl, bookmarkUrl } from "@/lib/bookmark-utils";
+
+/** 最近的搜索或搜索引擎的建议 */
+export interface SearchSuggestion {
+  kind: "recent" | "engine";
+  text: string;
+}
 
 interface SearchDropdownProps {
   results: Bookmark[];
+  suggestions?: SearchSuggestion[];
+  onSelectSuggestion?: (text: string) => void;
   searchQuery: string;
   isOpen: boolean;
   selectedIndex: number;
   onSelect: (bookmark: Bookmark) => void;
   onClose: () => void;
-  useIntranetUrl?: boolean;
+  zones?: string[];
 }
 
 function DropdownItem({
@@ -20,26 +29,24 @@ function DropdownItem({
   isSelected,
   onClick,
   itemRef,
-  useIntranetUrl = false,
+  zones = [],
 }: {
   bookmark: Bookmark;
   searchQuery: string;
   isSelected: boolean;
   onClick: () => void;
   itemRef: (el: HTMLAnchorElement | null) => void;
-  useIntranetUrl?: boolean;
+  zones?: string[];
 }) {
   const [iconError, setIconError] = useState(false);
 
-  // 根据内网状态决定使用哪个链接
-  const displayUrl =
-    useIntranetUrl && bookmark.intranet_url
-      ? bookmark.intranet_url
-      : bookmark.url;
+  // 根据网络区域决定使用哪个链接
+  const displayUrl = bookmarkUrl(bookmark, zones);
 
   return (
     <a
-      href={displayUrl}
+      href={bookmarkGoUrl(bookmark, zones, searchQuery)}
+      title={displayUrl}
       ref={itemRef}
       className={`
         flex items-center gap-3 px-4 py-3 cursor-pointer transition-colors no-underline text-foreground
@@ -58,7 +65,7 @@ function DropdownItem({
       <div className="shrink-0">
         {bookmark.icon && !iconError ? (
           <img
-            src={iconUrl(bookmark.icon)}
+            src={iconUrl(bookmark.icon, bookmark.id)}
             alt={bookmark.title}
             className="size-6 object-contain"
             onError={() => setIconError(true)}
@@ -87,16 +94,54 @@ function DropdownItem({
   );
 }
 
+function SuggestionItem({
+  suggestion,
+  searchQuery,
+  isSelected,
+  onClick,
+  itemRef,
+}: {
+  suggestion: SearchSuggestion;
+  searchQuery: string;
+  isSelected: boolean;
+  onClick: () => void;
+  itemRef: (el: HTMLDivElement | null) => void;
+}) {
+  const Icon = suggestion.kind === "recent" ? History : Search;
+
+  return (
+    <div
+      ref={itemRef}
+      className={`
+        flex items-center gap-3 px-4 py-2 cursor-pointer transition-colors text-sm
+        ${isSelected ? "bg-accent text-accent-foreground" : "hover:bg-muted"}
+      `}
+      onMouseDown={(e) => {
+        // 防止输入框失去焦点
+        e.preventDefault();
+        onClick();
+      }}
+    >
+      <Icon className="size-4 text-muted-foreground shrink-0" />
+      <span className="flex-1 min-w-0 truncate">
+        {highlightText(suggestion.text, searchQuery)}
+      </span>
+    </div>
+  );
+}
+
 export function SearchDropdown({
   results,
+  suggestions = [],
+  onSelectSuggestion,
   searchQuery,
   isOpen,
   selectedIndex,
   onSelect,
-  useIntranetUrl = false,
+  zones = [],
 }: SearchDropdownProps) {
   const listRef = useRef<HTMLDivElement>(null);
-  const itemRefs = useRef<(HTMLAnchorElement | null)[]>([]);
+  const itemRefs = useRef<(HTMLElement | null)[]>([]);
 
   // 滚动到选中项
   useEffect(() => {
@@ -108,7 +153,7 @@ export function SearchDropdown({
     }
   }, [selectedIndex, isOpen]);
 
-  if (!isOpen || results.length === 0) {
+  if (!isOpen || results.length + suggestions.length === 0) {
     return null;
   }
 
@@ -127,9 +172,24 @@ export function SearchDropdown({
           itemRef={(el) => {
             itemRefs.current[index] = el;
           }}
-          useIntranetUrl={useIntranetUrl}
+          zones={zones}
         />
       ))}
+      {suggestions.map((suggestion, i) => {
+        const index = results.length + i;
+        return (
+          <SuggestionItem
+            key={`${suggestion.kind}-${suggestion.text}`}
+            suggestion={suggestion}
+            searchQuery={searchQuery}
+            isSelected={index === selectedIndex}
+            onClick={() => onSelectSuggestion?.(suggestion.text)}
+            itemRef={(el) => {
+              itemRefs.current[index] = el;
+            }}
+          />
+        );
+      })}
     </div>
   );
 }
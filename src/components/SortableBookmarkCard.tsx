@@ -1,25 +1,32 @@
 import { useSortable } from "@dnd-kit/sortable";
 import { CSS } from "@dnd-kit/utilities";
 import { BookmarkCard } from "@/components/BookmarkCard";
-import type { Bookmark } from "@/types/bookmark";
+import type { Bookmark, HealthState } from "@/types/bookmark";
 import { cn } from "@/lib/utils";
 
 interface SortableBookmarkCardProps {
   bookmark: Bookmark;
   searchQuery?: string;
-  useIntranetUrl?: boolean;
+  zones?: string[];
   hasBackground?: boolean;
+  healthState?: HealthState;
+  certExpiring?: boolean;
   onEdit?: (bookmark: Bookmark) => void;
   onDelete?: (bookmark: Bookmark) => void;
+  /** 按使用情况排序时不能拖拽 */
+  disabled?: boolean;
 }
 
 export function SortableBookmarkCard({
   bookmark,
   searchQuery,
-  useIntranetUrl,
+  zones,
   hasBackground,
+  healthState,
+  certExpiring,
   onEdit,
   onDelete,
+  disabled = false,
 }: SortableBookmarkCardProps) {
   const {
     attributes,
@@ -28,7 +35,7 @@ export function SortableBookmarkCard({
     transform,
     transition,
     isDragging,
-  } = useSortable({ id: bookmark.id });
+  } = useSortable({ id: bookmark.id, disabled });
 
   const style = {
     transform: CSS.Transform.toString(transform),
@@ -42,7 +49,10 @@ export function SortableBookmarkCard({
       style={style}
       {...attributes}
       {...listeners}
-      className={cn("cursor-grab active:cursor-grabbing", isDragging && "z-50")}
+      className={cn(
+        !disabled && "cursor-grab active:cursor-grabbing",
+        isDragging && "z-50"
+      )}
     >
       <div
         style={{
@@ -52,8 +62,10 @@ export function SortableBookmarkCard({
         <BookmarkCard
           bookmark={bookmark}
           searchQuery={searchQuery}
-          useIntranetUrl={useIntranetUrl}
+          zones={zones}
           hasBackground={hasBackground}
+          healthState={healthState}
+          certExpiring={certExpiring}
           onEdit={onEdit}
           onDelete={onDelete}
         />
@@ -1,5 +1,12 @@
-import type { BackgroundImage, BookmarkProfile, ImageOrientation } from "@/types/bookmark";
-import { apiClient } from "./axios";
+import type {
+  BackgroundImage,
+  Bookmark,
+  BookmarkProfile,
+  BookmarkStatus,
+  ImageOrientation,
+  RewriteRule,
+} from "@/types/bookmark";
+import { API_BASE_URL, apiClient } from "./axios";
 import { useAppStore } from "./store";
 
 export async function login(username: string, password: string): Promise<void> {
@@ -54,6 +61,170 @@ export async function sortProfiles(profileNames: string[]): Promise<void> {
   await apiClient.post("/api/profile/sort", profileNames);
 }
 
+export type HistoryAction =
+  | "create"
+  | "update"
+  | "delete"
+  | "rename"
+  | "link_action"
+  | "restore";
+
+export interface Revision {
+  id: number;
+  profile: string;
+  at: string;
+  actor: { session?: string; address: string; user_agent?: string };
+  action: HistoryAction;
+  /** 不含书签的 profile 设置，null 表示 profile 不存在 */
+  settings?: {
+    before: BookmarkProfile | null;
+    after: BookmarkProfile | null;
+  };
+  bookmarks?: {
+    id: string;
+    before: Bookmark | null;
+    after: Bookmark | null;
+  }[];
+  order?: { before: string[]; after: string[] };
+}
+
+/**
+ * 获取 profile 的修改历史，最新的在前
+ * @param bookmark 只返回修改了该书签的记录
+ */
+export async function getProfileHistory(
+  profile: string,
+  bookmark?: string,
+  limit?: number
+): Promise<Revision[]> {
+  const response = await apiClient.get<Revision[]>("/api/profile/history", {
+    params: { profile, bookmark, limit },
+  });
+  return response.data;
+}
+
+/**
+ * 将 profile 恢复到某次修改后的状态
+ * @param bookmark 只恢复该书签
+ */
+export async function restoreProfile(
+  profile: string,
+  revision: number,
+  bookmark?: string
+): Promise<void> {
+  await apiClient.post("/api/profile/restore", { profile, revision, bookmark });
+}
+
+export interface RewritePreview {
+  id: string;
+  title: string;
+  url: string;
+  /** 各区域生成的链接 */
+  derived: Record<string, string>;
+  /** 书签自己设置了链接的区域 */
+  overridden: string[];
+}
+
+/**
+ * 预览重写规则为每个书签生成的链接
+ * @param profile Profile 名称
+ * @param rules 要预览的规则，不传则使用已保存的规则
+ */
+export async function previewUrlRewrites(
+  profile: string,
+  rules?: RewriteRule[]
+): Promise<RewritePreview[]> {
+  const response = await apiClient.post<RewritePreview[]>(
+    "/api/profile/rewrite-preview",
+    { rules },
+    { params: { profile } }
+  );
+  return response.data;
+}
+
+/**
+ * 获取书签的健康检查结果，没有检查结果的书签不会返回
+ */
+export async function getStatus(profile?: string): Promise<BookmarkStatus[]> {
+  const currentProfile = useAppStore.getState().currentProfile;
+  const profileToUse = profile ?? currentProfile ?? undefined;
+  const params = profileToUse ? { profile: profileToUse } : {};
+  const response = await apiClient.get<BookmarkStatus[]>("/api/status", {
+    params,
+  });
+  return response.data;
+}
+
+export interface UndoState {
+  /** 可以撤销的操作数量 */
+  undo: number;
+  /** 可以重做的操作数量 */
+  redo: number;
+}
+
+export interface UndoResponse extends UndoState {
+  operation: "create" | "update" | "delete" | "rename" | "sort";
+  profile?: string;
+}
+
+/**
+ * 查询当前会话可以撤销和重做的操作数量
+ */
+export async function getUndoState(): Promise<UndoState> {
+  const response = await apiClient.get<UndoState>("/api/undo");
+  return response.data;
+}
+
+/**
+ * 撤销当前会话最近的操作，数据已被其他设备修改时返回 409
+ */
+export async function undo(): Promise<UndoResponse> {
+  const response = await apiClient.post<UndoResponse>("/api/undo");
+  return response.data;
+}
+
+/**
+ * 重做最近撤销的操作
+ */
+export async function redo(): Promise<UndoResponse> {
+  const response = await apiClient.post<UndoResponse>("/api/redo");
+  return response.data;
+}
+
+export type ServerEventType =
+  | "profile-changed"
+  | "bookmark-changed"
+  | "background-changed"
+  | "status-changed";
+
+/**
+ * 订阅服务端的数据变更事件，返回取消订阅的函数
+ * 断线后浏览器会自动重连并带上 Last-Event-ID
+ */
+export function subscribeEvents(
+  onEvent: (type: ServerEventType) => void,
+  profile?: string
+): () => void {
+  const currentProfile = useAppStore.getState().currentProfile;
+  const profileToUse = profile ?? currentProfile ?? undefined;
+  const query = profileToUse
+    ? `?profile=${encodeURIComponent(profileToUse)}`
+    : "";
+  const source = new EventSource(`${API_BASE_URL}/api/events${query}`, {
+    withCredentials: true,
+  });
+  const types: ServerEventType[] = [
+    "profile-changed",
+    "bookmark-changed",
+    "background-changed",
+    "status-changed",
+  ];
+  for (const type of types) {
+    source.addEventListener(type, () => onEvent(type));
+  }
+  return () => source.close();
+}
+
 export async function setIcon(
   bookmarkId: string,
   iconFile: File
@@ -67,8 +238,152 @@ export async function setIcon(
   });
 }
 
-export function iconUrl(iconFilename: string): string {
-  return `${apiClient.defaults.baseURL}/images/icons/${iconFilename}`;
+/** 搜索框的建议 */
+export type SuggestItem =
+  | { kind: "bookmark"; text: string; title: string; id: string }
+  | { kind: "recent"; text: string }
+  | { kind: "engine"; text: string };
+
+/**
+ * 获取搜索建议，搜索引擎的建议由服务端代理
+ * @param query 搜索内容
+ * @param profile 使用该 Profile 的搜索引擎
+ */
+export async function getSearchSuggestions(
+  query: string,
+  profile?: string
+): Promise<SuggestItem[]> {
+  const response = await apiClient.get<SuggestItem[]>("/api/search/suggest", {
+    params: { q: query, profile },
+  });
+  return response.data;
+}
+
+/** 一条搜索历史 */
+export interface SearchHistoryEntry {
+  id: string;
+  at: string;
+  query: string;
+  profile: string;
+  engine?: string;
+  /** 打开或用来搜索的书签 */
+  bookmark?: string;
+}
+
+export interface SearchHistory {
+  enabled: boolean;
+  /** 超过天数的记录会被删除，0 表示永久保留 */
+  retention_days: number;
+  /** 最新的在前 */
+  entries: SearchHistoryEntry[];
+}
+
+/**
+ * 获取当前用户的搜索历史
+ */
+export async function getSearchHistory(): Promise<SearchHistory> {
+  const response = await apiClient.get<SearchHistory>("/api/search/history");
+  return response.data;
+}
+
+/**
+ * 删除一条搜索历史
+ * @param id 搜索历史 ID
+ */
+export async function deleteSearchHistoryEntry(id: string): Promise<void> {
+  await apiClient.delete(`/api/search/history/${id}`);
+}
+
+/**
+ * 清空当前用户的搜索历史
+ */
+export async function clearSearchHistory(): Promise<void> {
+  await apiClient.delete("/api/search/history");
+}
+
+/**
+ * 获取搜索的跳转链接，由服务端解析快捷方式和书签关键字
+ * @param query 搜索内容
+ * @param profile 使用该 Profile 的搜索引擎
+ */
+export function searchRedirectUrl(query: string, profile?: string): string {
+  const params = new URLSearchParams({ q: query });
+  if (profile) {
+    params.set("profile", profile);
+  }
+  return `${apiClient.defaults.baseURL}/api/search/redirect?${params}`;
+}
+
+/**
+ * 获取 OpenSearch 描述文件的链接
+ * @param profile 使用该 Profile 的搜索引擎
+ */
+export function openSearchUrl(profile: string): string {
+  const params = new URLSearchParams({ profile });
+  return `${apiClient.defaults.baseURL}/opensearch.xml?${params}`;
+}
+
+/**
+ * 获取书签的跳转链接，打开时服务端会记录使用次数
+ * @param bookmarkId 书签 ID
+ * @param zone 打开的网络区域，null 表示外网链接
+ * @param query 从搜索结果中打开时的搜索内容，会记录到搜索历史
+ */
+export function goUrl(
+  bookmarkId: string,
+  zone: string | null,
+  query?: string
+): string {
+  const params = new URLSearchParams({ zone: zone ?? "" });
+  if (query?.trim()) {
+    params.set("q", query.trim());
+  }
+  return `${apiClient.defaults.baseURL}/go/${bookmarkId}?${params}`;
+}
+
+/**
+ * 获取图标 URL
+ * @param icon 图标文件名、`generated` 开头的字母头像或 `library:` 开头的图标库路径
+ * @param bookmarkId 书签 ID（字母头像需要）
+ */
+export function iconUrl(icon: string, bookmarkId?: string): string {
+  if (bookmarkId && icon.startsWith("generated")) {
+    const options = icon.slice("generated".length);
+    return `${apiClient.defaults.baseURL}/api/images/generated/${bookmarkId}${options}`;
+  }
+  if (icon.startsWith("library:")) {
+    return `${apiClient.defaults.baseURL}/icon-library/${icon.slice("library:".length)}`;
+  }
+  return `${apiClient.defaults.baseURL}/images/icons/${icon}`;
+}
+
+export interface LibraryIcon {
+  name: string;
+  path: string;
+  variants: string[];
+}
+
+/**
+ * 在本地图标库中搜索图标
+ * @param q 图标名称
+ */
+export async function searchIconLibrary(q: string): Promise<LibraryIcon[]> {
+  const response = await apiClient.get<LibraryIcon[]>("/api/icon-library", {
+    params: { q },
+  });
+  return response.data;
+}
+
+/**
+ * 将图标库中的图标设置为书签图标
+ * @param bookmarkId 书签 ID
+ * @param path 图标库中的相对路径
+ */
+export async function setLibraryIcon(
+  bookmarkId: string,
+  path: string
+): Promise<void> {
+  await apiClient.post(`/api/images/icon/${bookmarkId}/library`, { path });
 }
 
 export interface FaviconIcon {
@@ -88,11 +403,22 @@ export async function fetchFavicon(url: string): Promise<FaviconResult> {
   return response.data;
 }
 
+export interface BackgroundImageSource {
+  type: string;
+  srcset: string;
+}
+
 export interface BackgroundImageInfo {
   id: string;
   filename: string;
   orientation: ImageOrientation;
   url: string;
+  width?: number;
+  height?: number;
+  sources: BackgroundImageSource[];
+  blurhash?: string;
+  dominant_color?: string;
+  text_color?: string;
 }
 
 export interface BackgroundImageListResponse {
@@ -141,6 +467,62 @@ export async function getBackgroundImages(
   return response.data;
 }
 
+export interface LibraryImageInfo extends BackgroundImageInfo {
+  profiles: string[];
+}
+
+/**
+ * 获取所有 profile 共用的背景图库
+ */
+export async function getBackgroundLibrary(): Promise<LibraryImageInfo[]> {
+  const response = await apiClient.get<LibraryImageInfo[]>(
+    "/api/background-library"
+  );
+  return response.data;
+}
+
+/**
+ * 将图库中的背景图添加到 profile
+ * @param profile Profile 名称
+ * @param imageId 背景图 ID
+ */
+export async function attachBackgroundImage(
+  profile: string,
+  imageId: string
+): Promise<BackgroundImage> {
+  const response = await apiClient.post<BackgroundImage>(
+    "/api/background-image/attach",
+    undefined,
+    {
+      params: { profile, id: imageId },
+    }
+  );
+  return response.data;
+}
+
+export interface CurrentBackgroundImageResponse {
+  image: BackgroundImageInfo | null;
+  next_change_at: string;
+}
+
+/**
+ * 按轮换规则获取当前的背景图
+ * @param profile Profile 名称
+ * @param orientation 设备方向
+ */
+export async function getCurrentBackgroundImage(
+  profile: string,
+  orientation?: ImageOrientation
+): Promise<CurrentBackgroundImageResponse> {
+  const response = await apiClient.get<CurrentBackgroundImageResponse>(
+    "/api/background-image/current",
+    {
+      params: { profile, orientation },
+    }
+  );
+  return response.data;
+}
+
 /**
  * 删除背景图
  * @param profile Profile 名称
@@ -165,3 +547,136 @@ export async function deleteBackgroundImage(
 export function backgroundImageUrl(filename: string): string {
   return `${apiClient.defaults.baseURL}/images/backgrounds/${filename}`;
 }
+
+/**
+ * 选择不小于目标宽度的最小缩放图，没有缩放图时返回原图
+ * @param image 背景图
+ * @param targetWidth 目标宽度（物理像素）
+ */
+export function bestBackgroundImageUrl(
+  image: BackgroundImage,
+  targetWidth: number
+): string {
+  const variants = (image.variants ?? [])
+    .filter((v) => v.format === "Avif")
+    .sort((a, b) => a.width - b.width);
+  if (variants.length === 0) {
+    return backgroundImageUrl(image.filename);
+  }
+  const variant =
+    variants.find((v) => v.width >= targetWidth) ??
+    variants[variants.length - 1];
+  // 原图比最大的缩放图更清晰
+  if (variant.width < targetWidth && (image.width ?? 0) > variant.width) {
+    return backgroundImageUrl(image.filename);
+  }
+  return backgroundImageUrl(variant.filename);
+}
+
+export type LinkStatus =
+  | { kind: "Ok"; status: number }
+  | { kind: "Redirect"; status: number; target: string; permanent: boolean }
+  | { kind: "ClientError"; status: number }
+  | { kind: "ServerError"; status: number }
+  | { kind: "DnsFailure"; error: string }
+  | { kind: "Timeout" }
+  | { kind: "ConnectionError"; error: string };
+
+export type LinkResult = LinkStatus & {
+  id: string;
+  title: string;
+  url: string;
+  broken: boolean;
+};
+
+export interface LinkReport {
+  started_at: string;
+  /** 检查进行中时为空 */
+  finished_at?: string;
+  total: number;
+  results: LinkResult[];
+}
+
+export type LinkAction = "update_redirect" | "tag_broken" | "delete";
+
+/**
+ * 获取最近一次链接检查的结果
+ */
+export async function getLinkReport(
+  profile: string
+): Promise<LinkReport | null> {
+  const response = await apiClient.get<LinkReport | null>(
+    "/api/maintenance/links",
+    { params: { profile } }
+  );
+  return response.data;
+}
+
+/**
+ * 开始在后台检查 profile 中所有书签的链接
+ */
+export async function startLinkAudit(profile: string): Promise<LinkReport> {
+  const response = await apiClient.post<LinkReport>(
+    "/api/maintenance/links",
+    null,
+    { params: { profile } }
+  );
+  return response.data;
+}
+
+/**
+ * 批量处理链接检查结果中的书签
+ * @returns 修改的书签数量
+ */
+export async function applyLinkAction(
+  profile: string,
+  action: LinkAction,
+  ids: string[],
+  tag?: string
+): Promise<number> {
+  const response = await apiClient.post<{ updated: number }>(
+    "/api/maintenance/links/actions",
+    { action, ids, tag },
+    { params: { profile } }
+  );
+  return response.data.updated;
+}
+
+export type TrashEntry = {
+  id: string;
+  deleted_at: string;
+} & (
+  | { kind: "bookmark"; profile: string; bookmark: Bookmark; index: number }
+  | { kind: "profile"; profile: BookmarkProfile; index: number }
+  | { kind: "background_image"; image: BackgroundImage; profiles: string[] }
+);
+
+/**
+ * 获取回收站中的条目，最近删除的在前
+ */
+export async function getTrash(): Promise<TrashEntry[]> {
+  const response = await apiClient.get<TrashEntry[]>("/api/trash");
+  return response.data;
+}
+
+/**
+ * 从回收站恢复到原来的位置
+ */
+export async function restoreTrashEntry(id: string): Promise<void> {
+  await apiClient.post(`/api/trash/${id}/restore`);
+}
+
+/**
+ * 彻底删除回收站中的条目
+ */
+export async function purgeTrashEntry(id: string): Promise<void> {
+  await apiClient.delete(`/api/trash/${id}`);
+}
+
+/**
+ * 清空回收站，返回删除的条目数量
+ */
+export async function emptyTrash(): Promise<number> {
+  const response = await apiClient.delete<number>("/api/trash");
+  return response.data;
+}
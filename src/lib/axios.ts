@@ -1,7 +1,7 @@
 import axios from "axios";
 import i18n from "./i18n";
 
-const API_BASE_URL = import.meta.env.VITE_API_URL || "";
+export const API_BASE_URL = import.meta.env.VITE_API_URL || "";
 
 // 创建 axios 实例
 export const apiClient = axios.create({
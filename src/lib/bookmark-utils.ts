@@ -1,4 +1,5 @@
-import type { Bookmark, BookmarkProfile } from "@/types/bookmark";
+import type { Bookmark, BookmarkProfile, SortMode } from "@/types/bookmark";
+import { goUrl } from "@/lib/api";
 
 /**
  * Calculate and update tags based on the bookmarks list
@@ -62,3 +63,97 @@ export function updateProfileTags(profile: BookmarkProfile): BookmarkProfile {
     tags: updatedTags,
   };
 }
+
+/** intranet_url 对应的网络区域 */
+export const INTRANET_ZONE = "intranet";
+
+/**
+ * List the links of a bookmark for each network zone
+ * - intranet_url 作为 intranet 区域的链接
+ * - 没有设置链接的区域使用重写规则生成的链接
+ *
+ * @param bookmark The bookmark
+ * @returns [zone, url] pairs, not including the default url
+ */
+export function bookmarkZoneUrls(bookmark: Bookmark): [string, string][] {
+  const entries: [string, string][] = [];
+  if (bookmark.intranet_url) {
+    entries.push([INTRANET_ZONE, bookmark.intranet_url]);
+  }
+  for (const [zone, url] of Object.entries(bookmark.zone_urls ?? {})) {
+    if (zone !== INTRANET_ZONE && url) {
+      entries.push([zone, url]);
+    }
+  }
+  for (const [zone, url] of Object.entries(bookmark.derived_urls ?? {})) {
+    if (!entries.some(([z]) => z === zone)) {
+      entries.push([zone, url]);
+    }
+  }
+  return entries;
+}
+
+/**
+ * Pick the zone whose link is used for the current network zones
+ *
+ * @param bookmark The bookmark
+ * @param zones The current zones, in order of preference
+ * @returns The first zone the bookmark has a link for, or null for the default url
+ */
+export function bookmarkZone(
+  bookmark: Bookmark,
+  zones: string[] = []
+): string | null {
+  const urls = new Map(bookmarkZoneUrls(bookmark));
+  return zones.find((zone) => urls.get(zone)) ?? null;
+}
+
+/**
+ * Pick the link for the current network zones
+ *
+ * @param bookmark The bookmark
+ * @param zones The current zones, in order of preference
+ * @returns The link of the first zone the bookmark has one for, or the default url
+ */
+export function bookmarkUrl(bookmark: Bookmark, zones: string[] = []): string {
+  const zone = bookmarkZone(bookmark, zones);
+  return zone === null
+    ? bookmark.url
+    : (new Map(bookmarkZoneUrls(bookmark)).get(zone) ?? bookmark.url);
+}
+
+/**
+ * The /go link of a bookmark for the current network zones, counts the open
+ *
+ * @param bookmark The bookmark
+ * @param zones The current zones, in order of preference
+ * @param query The search the bookmark was chosen from, if any
+ */
+export function bookmarkGoUrl(
+  bookmark: Bookmark,
+  zones: string[] = [],
+  query?: string
+): string {
+  return goUrl(bookmark.id, bookmarkZone(bookmark, zones), query);
+}
+
+/**
+ * Order bookmarks by how they are used, keeps the manual order for ties
+ *
+ * @param bookmarks The bookmarks in manual order
+ * @param mode The sort mode of the profile
+ */
+export function sortBookmarks(
+  bookmarks: Bookmark[],
+  mode: SortMode = "Manual"
+): Bookmark[] {
+  if (mode === "Manual") {
+    return bookmarks;
+  }
+  const score = (bookmark: Bookmark) =>
+    mode === "Frecency"
+      ? (bookmark.stats?.frecency ?? 0)
+      : (bookmark.stats?.count ?? 0);
+  // Array.prototype.sort 是稳定排序
+  return [...bookmarks].sort((a, b) => score(b) - score(a));
+}
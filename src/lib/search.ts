@@ -138,7 +138,12 @@ function calculateBookmarkScore(
 
   const matches = titleResult.matches || urlResult.matches || bestTagResult.matches;
 
-  return { matches, score: totalScore };
+  // 常用的书签排在前面，但不会超过明显更匹配的结果
+  const usageScore = Math.min(Math.log2(1 + (bookmark.stats?.frecency ?? 0)) * 10, 150);
+  // 经常从搜索中选择的书签也排在前面
+  const searchScore = Math.min((bookmark.stats?.searches ?? 0) * 20, 100);
+
+  return { matches, score: totalScore + usageScore + searchScore };
 }
 
 /**
@@ -1,7 +1,13 @@
 import { useState, useEffect } from "react";
 import { useTranslation } from "react-i18next";
-import type { Bookmark, BookmarkProfile } from "@/types/bookmark";
+import type {
+  BackgroundImage,
+  Bookmark,
+  BookmarkProfile,
+  BookmarkStatus,
+} from "@/types/bookmark";
 import { SearchBox } from "@/components/SearchBox";
+import type { SearchSuggestion } from "@/components/SearchDropdown";
 import { FloatingActionButton } from "@/components/FloatingActionButton";
 import { SortableBookmarkCard } from "@/components/SortableBookmarkCard";
 import { SortableTagButton } from "@/components/SortableTagButton";
@@ -21,9 +27,25 @@ import {
   sortableKeyboardCoordinates,
   rectSortingStrategy,
 } from "@dnd-kit/sortable";
-import { getProfile, updateProfile, backgroundImageUrl } from "@/lib/api";
+import {
+  getProfile,
+  updateProfile,
+  bestBackgroundImageUrl,
+  getCurrentBackgroundImage,
+  getStatus,
+  subscribeEvents,
+  undo,
+  redo,
+  searchRedirectUrl,
+  openSearchUrl,
+  getSearchSuggestions,
+} from "@/lib/api";
 import { fuzzySearch } from "@/lib/search";
-import { updateProfileTags } from "@/lib/bookmark-utils";
+import {
+  INTRANET_ZONE,
+  sortBookmarks,
+  updateProfileTags,
+} from "@/lib/bookmark-utils";
 import { useNavigate } from "react-router-dom";
 import { useMediaQuery } from "@/hooks/use-media-query";
 
@@ -42,8 +64,17 @@ export function HomePage() {
   );
   const [selectedSearchBookmark, setSelectedSearchBookmark] =
     useState<Bookmark | null>(null);
-  const [isIntranet, setIsIntranet] = useState(false);
+  // 当前所在的网络区域（按优先级），为空表示外网
+  const [networkZones, setNetworkZones] = useState<string[]>([]);
+  const [suggestions, setSuggestions] = useState<SearchSuggestion[]>([]);
+  const isIntranet = networkZones.length > 0;
+  // 书签 ID -> 服务端健康检查结果
+  const [healthStatuses, setHealthStatuses] = useState<
+    Record<string, BookmarkStatus>
+  >({});
   const [backgroundImage, setBackgroundImage] = useState<string | null>(null);
+  // 图片加载前先显示主色调
+  const [backgroundColor, setBackgroundColor] = useState<string | null>(null);
   const navigate = useNavigate();
 
   // 检测设备方向（横屏/竖屏）
@@ -68,15 +99,19 @@ export function HomePage() {
       setBookmarks(profileData.bookmarks);
       setTags([t("common.all"), ...profileData.tags]);
 
-      // 检测内网环境
-      if (profileData.intranet_check_url) {
+      // 检测内网环境，优先使用服务端的判断结果
+      if (profileData.network_zones) {
+        setNetworkZones(profileData.network_zones);
+      } else if (profileData.intranet_check_url) {
         checkIntranet(profileData.intranet_check_url);
       } else {
-        setIsIntranet(false);
+        setNetworkZones([]);
       }
 
       // 选择背景图（根据设备方向随机选择）
       selectBackgroundImage(profileData);
+
+      loadHealthStates();
     } catch (error) {
       console.error(t("homePage.loadBookmarksFailed"), error);
     } finally {
@@ -84,26 +119,62 @@ export function HomePage() {
     }
   };
 
+  // 加载健康检查结果，失败时不显示状态
+  const loadHealthStates = async () => {
+    try {
+      const statuses = await getStatus();
+      setHealthStatuses(
+        Object.fromEntries(statuses.map((status) => [status.id, status]))
+      );
+    } catch {
+      setHealthStatuses({});
+    }
+  };
+
   // 选择背景图（根据设备方向随机选择）
-  const selectBackgroundImage = (profileData: BookmarkProfile) => {
+  const selectBackgroundImage = async (profileData: BookmarkProfile) => {
     const images = profileData.background_images || [];
     if (images.length === 0) {
       setBackgroundImage(null);
+      setBackgroundColor(null);
       return;
     }
 
-    // 根据设备方向筛选图片
     const orientation = isLandscape ? "Landscape" : "Portrait";
-    const matchingImages = images.filter(
-      (img) => img.orientation === orientation
-    );
+    let selectedImage: BackgroundImage | undefined;
+
+    // 配置了轮换规则时由服务端决定，所有设备显示同一张
+    if (profileData.background_rotation) {
+      try {
+        const current = await getCurrentBackgroundImage(
+          profileData.name,
+          orientation
+        );
+        selectedImage = images.find((img) => img.id === current.image?.id);
+      } catch (error) {
+        console.error(error);
+      }
+    }
 
-    // 如果没有匹配的图片，从所有图片中随机选择
-    const pool = matchingImages.length > 0 ? matchingImages : images;
-    const randomIndex = Math.floor(Math.random() * pool.length);
-    const selectedImage = pool[randomIndex];
+    if (!selectedImage) {
+      // 根据设备方向筛选图片，方图横竖屏都适用
+      const matchingImages = images.filter(
+        (img) => img.orientation === orientation || img.orientation === "Square"
+      );
 
-    setBackgroundImage(backgroundImageUrl(selectedImage.filename));
+      // 如果没有匹配的图片，从所有图片中随机选择
+      const pool = matchingImages.length > 0 ? matchingImages : images;
+      const randomIndex = Math.floor(Math.random() * pool.length);
+      selectedImage = pool[randomIndex];
+    }
+
+    setBackgroundImage(
+      bestBackgroundImageUrl(
+        selectedImage,
+        window.innerWidth * window.devicePixelRatio
+      )
+    );
+    setBackgroundColor(selectedImage.dominant_color ?? null);
   };
 
   // 检测是否在内网环境
@@ -119,10 +190,10 @@ export function HomePage() {
       });
 
       clearTimeout(timeoutId);
-      setIsIntranet(true);
+      setNetworkZones([INTRANET_ZONE]);
     } catch {
       // 请求失败或超时，认为不在内网
-      setIsIntranet(false);
+      setNetworkZones([]);
     }
   };
 
@@ -130,11 +201,109 @@ export function HomePage() {
     loadData();
   }, []);
 
+  // 浏览器添加搜索引擎时使用当前 Profile 的搜索引擎
+  useEffect(() => {
+    if (!profile?.name) return;
+    const link = document.querySelector<HTMLLinkElement>('link[rel="search"]');
+    if (link) {
+      link.href = openSearchUrl(profile.name);
+    }
+  }, [profile?.name]);
+
+  // 输入时获取最近的搜索和搜索引擎的建议
+  useEffect(() => {
+    const query = searchQuery.trim();
+    if (!profile || !query || selectedSearchBookmark) {
+      setSuggestions([]);
+      return;
+    }
+    let cancelled = false;
+    const timer = setTimeout(async () => {
+      try {
+        const items = await getSearchSuggestions(query, profile.name);
+        if (cancelled) return;
+        // 书签由本地的模糊搜索显示
+        setSuggestions(
+          items.flatMap((item) =>
+            item.kind === "bookmark"
+              ? []
+              : [{ kind: item.kind, text: item.text }]
+          )
+        );
+      } catch (error) {
+        console.error("Failed to get search suggestions:", error);
+      }
+    }, 200);
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [searchQuery, profile?.name, selectedSearchBookmark]);
+
+  // 定期刷新健康检查结果
+  useEffect(() => {
+    const timer = setInterval(loadHealthStates, 60 * 1000);
+    return () => clearInterval(timer);
+  }, []);
+
+  // 其他设备修改数据后实时刷新，短时间内的多个事件合并为一次加载
+  useEffect(() => {
+    if (!profile?.name) return;
+    let dataTimer: ReturnType<typeof setTimeout> | undefined;
+    let statusTimer: ReturnType<typeof setTimeout> | undefined;
+    const unsubscribe = subscribeEvents((type) => {
+      if (type === "status-changed") {
+        clearTimeout(statusTimer);
+        statusTimer = setTimeout(loadHealthStates, 1000);
+      } else {
+        clearTimeout(dataTimer);
+        dataTimer = setTimeout(loadData, 300);
+      }
+    }, profile.name);
+    return () => {
+      unsubscribe();
+      clearTimeout(dataTimer);
+      clearTimeout(statusTimer);
+    };
+  }, [profile?.name]);
+
+  // Ctrl+Z 撤销，Ctrl+Shift+Z 或 Ctrl+Y 重做，输入框中保留浏览器的默认行为
+  useEffect(() => {
+    const handleKeyDown = async (event: KeyboardEvent) => {
+      const target = event.target as HTMLElement | null;
+      if (
+        target?.isContentEditable ||
+        target?.tagName === "INPUT" ||
+        target?.tagName === "TEXTAREA"
+      ) {
+        return;
+      }
+      if (!(event.ctrlKey || event.metaKey)) return;
+      const key = event.key.toLowerCase();
+      const isRedo = (key === "z" && event.shiftKey) || key === "y";
+      if (key !== "z" && !isRedo) return;
+
+      event.preventDefault();
+      try {
+        await (isRedo ? redo() : undo());
+        loadData();
+      } catch (error) {
+        console.error(t("homePage.undoFailed"), error);
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, []);
+
+  // Order bookmarks by usage unless the profile is sorted manually
+  const sortMode = profile?.sort_mode ?? "Manual";
+  const sortedBookmarks = sortBookmarks(bookmarks, sortMode);
+
   // Filter bookmarks by search query
   // Do not filter bookmarks if a search bookmark is selected
   const searchFiltered = selectedSearchBookmark
-    ? bookmarks
-    : fuzzySearch(bookmarks, searchQuery);
+    ? sortedBookmarks
+    : fuzzySearch(sortedBookmarks, searchQuery);
 
   // Filter bookmarks by tag
   const filteredBookmarks = searchFiltered.filter((bookmark) => {
@@ -239,22 +408,16 @@ export function HomePage() {
     e.preventDefault();
     // Enter key to search
     if (searchQuery.trim()) {
-      let searchEngineTemplate: string;
       if (selectedSearchBookmark?.search_url) {
         // Use the selected bookmark's search engine
-        searchEngineTemplate = selectedSearchBookmark.search_url;
+        window.location.href = selectedSearchBookmark.search_url.replace(
+          "{}",
+          encodeURIComponent(searchQuery)
+        );
       } else if (profile) {
-        // Use the default search engine
-        searchEngineTemplate = profile.search_engine;
-      } else {
-        return;
+        // The server picks the engine from bangs and bookmark keywords
+        window.location.href = searchRedirectUrl(searchQuery, profile.name);
       }
-
-      const searchUrl = searchEngineTemplate.replace(
-        "{}",
-        encodeURIComponent(searchQuery)
-      );
-      window.location.href = searchUrl;
     }
   };
 
@@ -307,7 +470,10 @@ export function HomePage() {
       className="min-h-screen bg-background bg-cover bg-center bg-no-repeat bg-fixed"
       style={
         backgroundImage
-          ? { backgroundImage: `url(${backgroundImage})` }
+          ? {
+              backgroundImage: `url(${backgroundImage})`,
+              backgroundColor: backgroundColor ?? undefined,
+            }
           : undefined
       }
     >
@@ -322,9 +488,15 @@ export function HomePage() {
               ? [] // Do not show search results if a search bookmark is selected
               : searchFiltered.slice(0, 10) // Limit to 10 results
           }
+          suggestions={selectedSearchBookmark ? [] : suggestions}
+          onSelectSuggestion={(text) => {
+            if (profile) {
+              window.location.href = searchRedirectUrl(text, profile.name);
+            }
+          }}
           onSelectedBookmarkChange={setSelectedSearchBookmark}
           hasBackground={hasBackground}
-          useIntranetUrl={isIntranet}
+          zones={networkZones}
           className="max-w-2xl mx-auto mb-12"
         />
 
@@ -377,10 +549,13 @@ export function HomePage() {
                       key={bookmark.id}
                       bookmark={bookmark}
                       searchQuery={selectedSearchBookmark ? "" : searchQuery} // Do not highlight the title if a search bookmark is selected
-                      useIntranetUrl={isIntranet}
+                      zones={networkZones}
                       hasBackground={hasBackground}
+                      healthState={healthStatuses[bookmark.id]?.state}
+                      certExpiring={healthStatuses[bookmark.id]?.cert_expiring}
                       onEdit={handleEditBookmark}
                       onDelete={handleDeleteBookmark}
+                      disabled={sortMode !== "Manual"}
                     />
                   ))}
                 </div>
@@ -409,7 +584,9 @@ export function HomePage() {
           setLoading(true);
           loadData();
         }}
-        hasIntranetCheck={!!profile?.intranet_check_url}
+        hasIntranetCheck={
+          !!profile?.intranet_check_url || !!profile?.network_rules
+        }
         isIntranet={isIntranet}
       />
       {/* New/Edit bookmark dialog */}
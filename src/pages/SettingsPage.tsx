@@ -48,14 +48,21 @@ import {
   getBackgroundImages,
   deleteBackgroundImage,
   backgroundImageUrl,
+  getSearchHistory,
+  deleteSearchHistoryEntry,
+  clearSearchHistory,
 } from "@/lib/api";
-import type { BackgroundImageInfo } from "@/lib/api";
+import type { BackgroundImageInfo, SearchHistory } from "@/lib/api";
 import {
   useClearAll,
   useCurrentProfile,
   useSetCurrentProfile,
 } from "@/lib/store";
-import type { BookmarkProfile } from "@/types/bookmark";
+import type {
+  BookmarkProfile,
+  SearchEngine,
+  SortMode,
+} from "@/types/bookmark";
 import {
   ArrowLeft,
   LogOut,
@@ -75,30 +82,40 @@ function getSearchEnginePresets(t: (key: string) => string) {
       value: "google",
       label: t("settingsPage.searchEngines.google"),
       url: "https://www.google.com/search?q={}",
+      shortcut: "g",
     },
     {
       value: "baidu",
       label: t("settingsPage.searchEngines.baidu"),
       url: "https://www.baidu.com/s?wd={}",
+      shortcut: "bd",
     },
     {
       value: "bing",
       label: t("settingsPage.searchEngines.bing"),
       url: "https://www.bing.com/search?q={}",
+      shortcut: "b",
     },
     {
       value: "custom",
       label: t("settingsPage.searchEngines.custom"),
       url: "",
+      shortcut: "",
     },
   ];
 }
 
-// 根据 URL 获取搜索引擎类型
-function getSearchEngineType(url: string, t: (key: string) => string): string {
-  const presets = getSearchEnginePresets(t);
-  const preset = presets.find((p) => p.url === url);
-  return preset?.value || "custom";
+// 根据预设创建搜索引擎，自定义时名称和链接留空
+function searchEngineFromPreset(
+  value: string,
+  t: (key: string) => string
+): SearchEngine {
+  const preset = getSearchEnginePresets(t).find((p) => p.value === value);
+  return {
+    name: preset && preset.value !== "custom" ? preset.label : "",
+    url: preset?.url ?? "",
+    shortcut: preset?.shortcut || undefined,
+  };
 }
 
 // 可排序的 Profile 项
@@ -184,8 +201,9 @@ function SortableProfileItem({
 // Profile 保存参数
 interface ProfileSaveParams {
   name: string;
-  searchEngine: string;
+  searchEngines: SearchEngine[];
   intranetCheckUrl?: string;
+  sortMode: SortMode;
   originalName?: string; // 原始名称，用于判断是否需要改名
 }
 
@@ -206,9 +224,9 @@ function ProfileDialog({
   const { t } = useTranslation();
   const { Modal, isMobile } = useResponsiveModal();
   const [name, setName] = useState("");
-  const [searchEngineType, setSearchEngineType] = useState("google");
-  const [customSearchEngine, setCustomSearchEngine] = useState("");
+  const [searchEngines, setSearchEngines] = useState<SearchEngine[]>([]);
   const [intranetCheckUrl, setIntranetCheckUrl] = useState("");
+  const [sortMode, setSortMode] = useState<SortMode>("Manual");
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState("");
   const [backgroundImages, setBackgroundImages] = useState<
@@ -220,23 +238,20 @@ function ProfileDialog({
   useEffect(() => {
     if (open && profile) {
       setName(profile.name);
-      const type = getSearchEngineType(profile.search_engine, t);
-      setSearchEngineType(type);
-      if (type === "custom") {
-        setCustomSearchEngine(profile.search_engine);
-      } else {
-        setCustomSearchEngine("");
-      }
+      setSearchEngines(profile.search_engines ?? []);
       setIntranetCheckUrl(profile.intranet_check_url || "");
+      setSortMode(profile.sort_mode ?? "Manual");
       // 加载背景图列表
       loadBackgroundImages(profile.name);
       setPendingImages([]); // 重置待上传图片
       setPendingDeletes([]); // 重置待删除图片
     } else if (open && isNew) {
       setName("");
-      setSearchEngineType("google");
-      setCustomSearchEngine("");
+      setSearchEngines([
+        { ...searchEngineFromPreset("google", t), default: true },
+      ]);
       setIntranetCheckUrl("");
+      setSortMode("Manual");
       setBackgroundImages([]);
       setPendingImages([]);
       setPendingDeletes([]);
@@ -285,8 +300,9 @@ function ProfileDialog({
   const visibleImages = backgroundImages.filter(
     (img) => !pendingDeletes.includes(img.id)
   );
+  // 方图横竖屏都适用，放在横图列表中
   const landscapeImages = visibleImages.filter(
-    (img) => img.orientation === "Landscape"
+    (img) => img.orientation === "Landscape" || img.orientation === "Square"
   );
   const portraitImages = visibleImages.filter(
     (img) => img.orientation === "Portrait"
@@ -306,6 +322,29 @@ function ProfileDialog({
     };
   }, [pendingImages]);
 
+  const updateSearchEngine = (
+    index: number,
+    changes: Partial<SearchEngine>
+  ) => {
+    setSearchEngines((engines) =>
+      engines.map((engine, i) =>
+        i === index ? { ...engine, ...changes } : engine
+      )
+    );
+  };
+
+  // 没有标记默认时使用第一个搜索引擎
+  const isDefaultEngine = (index: number) =>
+    searchEngines.some((engine) => engine.default)
+      ? !!searchEngines[index].default
+      : index === 0;
+
+  const setDefaultSearchEngine = (index: number) => {
+    setSearchEngines((engines) =>
+      engines.map((engine, i) => ({ ...engine, default: i === index }))
+    );
+  };
+
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     if (!name.trim()) {
@@ -313,21 +352,27 @@ function ProfileDialog({
       return;
     }
 
-    let searchEngine = "";
-    if (searchEngineType === "custom") {
-      if (!customSearchEngine.trim()) {
-        setError(t("settingsPage.profileDialog.customSearchUrlRequired"));
-        return;
-      }
-      if (!customSearchEngine.includes("{}")) {
-        setError(t("settingsPage.profileDialog.searchUrlPlaceholderRequired"));
-        return;
-      }
-      searchEngine = customSearchEngine.trim();
-    } else {
-      const presets = getSearchEnginePresets(t);
-      const preset = presets.find((p) => p.value === searchEngineType);
-      searchEngine = preset?.url || "";
+    const engines = searchEngines.map((engine) => ({
+      ...engine,
+      name: engine.name.trim(),
+      url: engine.url.trim(),
+      shortcut: engine.shortcut?.trim().replace(/^!/, "") || undefined,
+      suggest_url: engine.suggest_url?.trim() || undefined,
+    }));
+    if (engines.some((engine) => !engine.name || !engine.url)) {
+      setError(t("settingsPage.profileDialog.searchEngineRequired"));
+      return;
+    }
+    if (engines.some((engine) => !engine.url.includes("{}"))) {
+      setError(t("settingsPage.profileDialog.searchUrlPlaceholderRequired"));
+      return;
+    }
+    const shortcuts = engines
+      .map((engine) => engine.shortcut?.toLowerCase())
+      .filter((shortcut) => shortcut);
+    if (new Set(shortcuts).size !== shortcuts.length) {
+      setError(t("settingsPage.profileDialog.duplicateShortcut"));
+      return;
     }
 
     setLoading(true);
@@ -336,8 +381,9 @@ function ProfileDialog({
       // 1. 先保存 profile（如果名称改变，会先改名，然后再更新其他设置）
       await onSave({
         name: name.trim(),
-        searchEngine,
+        searchEngines: engines,
         intranetCheckUrl: intranetCheckUrl.trim() || undefined,
+        sortMode,
         originalName: profile?.name, // 传递原始名称，用于判断是否需要改名
       });
 
@@ -416,16 +462,86 @@ function ProfileDialog({
           <div className="h-2"></div>
 
           <div className="space-y-2">
-            <Label>{t("settingsPage.profileDialog.defaultSearchEngine")}</Label>
+            <Label>{t("settingsPage.profileDialog.searchEngines")}</Label>
+            {searchEngines.map((engine, index) => (
+              <div key={index} className="space-y-2 rounded-md border p-2">
+                <div className="flex items-center gap-2">
+                  <Input
+                    value={engine.name}
+                    onChange={(e) =>
+                      updateSearchEngine(index, { name: e.target.value })
+                    }
+                    placeholder={t("common.name")}
+                    disabled={loading}
+                  />
+                  <Input
+                    value={engine.shortcut ?? ""}
+                    onChange={(e) =>
+                      updateSearchEngine(index, { shortcut: e.target.value })
+                    }
+                    placeholder={t(
+                      "settingsPage.profileDialog.shortcutPlaceholder"
+                    )}
+                    className="w-24 shrink-0"
+                    disabled={loading}
+                  />
+                  <Button
+                    type="button"
+                    variant={isDefaultEngine(index) ? "default" : "outline"}
+                    size="sm"
+                    onClick={() => setDefaultSearchEngine(index)}
+                    disabled={loading}
+                  >
+                    {t("settingsPage.profileDialog.defaultEngine")}
+                  </Button>
+                  <Button
+                    type="button"
+                    variant="ghost"
+                    size="icon"
+                    onClick={() =>
+                      setSearchEngines((engines) =>
+                        engines.filter((_, i) => i !== index)
+                      )
+                    }
+                    disabled={loading}
+                  >
+                    <X className="h-4 w-4" />
+                  </Button>
+                </div>
+                <Input
+                  value={engine.url}
+                  onChange={(e) =>
+                    updateSearchEngine(index, { url: e.target.value })
+                  }
+                  placeholder={t(
+                    "settingsPage.profileDialog.customSearchUrlPlaceholder"
+                  )}
+                  disabled={loading}
+                />
+                <Input
+                  value={engine.suggest_url ?? ""}
+                  onChange={(e) =>
+                    updateSearchEngine(index, { suggest_url: e.target.value })
+                  }
+                  placeholder={t(
+                    "settingsPage.profileDialog.suggestUrlPlaceholder"
+                  )}
+                  disabled={loading}
+                />
+              </div>
+            ))}
             <Select
-              value={searchEngineType}
-              onValueChange={setSearchEngineType}
+              value=""
+              onValueChange={(value) =>
+                setSearchEngines((engines) => [
+                  ...engines,
+                  searchEngineFromPreset(value, t),
+                ])
+              }
             >
               <SelectTrigger className="w-full">
                 <SelectValue
-                  placeholder={t(
-                    "settingsPage.profileDialog.selectSearchEngine"
-                  )}
+                  placeholder={t("settingsPage.profileDialog.addSearchEngine")}
                 />
               </SelectTrigger>
               <SelectContent>
@@ -436,28 +552,11 @@ function ProfileDialog({
                 ))}
               </SelectContent>
             </Select>
+            <p className="text-xs text-muted-foreground">
+              {t("settingsPage.profileDialog.searchEnginesHint")}
+            </p>
           </div>
 
-          {searchEngineType === "custom" && (
-            <div className="space-y-2">
-              <Label htmlFor="custom-search">
-                {t("settingsPage.profileDialog.customSearchUrl")}
-              </Label>
-              <Input
-                id="custom-search"
-                value={customSearchEngine}
-                onChange={(e) => setCustomSearchEngine(e.target.value)}
-                placeholder={t(
-                  "settingsPage.profileDialog.customSearchUrlPlaceholder"
-                )}
-                disabled={loading}
-              />
-              <p className="text-xs text-muted-foreground">
-                {t("settingsPage.profileDialog.searchUrlPlaceholderHint")}
-              </p>
-            </div>
-          )}
-
           <div className="space-y-2">
             <Label htmlFor="intranet-check-url">
               {t("settingsPage.profileDialog.intranetCheckUrl")}
@@ -476,6 +575,32 @@ function ProfileDialog({
             </p>
           </div>
 
+          <div className="space-y-2">
+            <Label>{t("settingsPage.profileDialog.sortMode")}</Label>
+            <Select
+              value={sortMode}
+              onValueChange={(value) => setSortMode(value as SortMode)}
+            >
+              <SelectTrigger className="w-full">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="Manual">
+                  {t("settingsPage.profileDialog.sortModes.manual")}
+                </SelectItem>
+                <SelectItem value="Frecency">
+                  {t("settingsPage.profileDialog.sortModes.frecency")}
+                </SelectItem>
+                <SelectItem value="MostUsed">
+                  {t("settingsPage.profileDialog.sortModes.mostUsed")}
+                </SelectItem>
+              </SelectContent>
+            </Select>
+            <p className="text-xs text-muted-foreground">
+              {t("settingsPage.profileDialog.sortModeHint")}
+            </p>
+          </div>
+
           {/* 背景图管理 */}
           {(isNew || profile) && (
             <div className="space-y-4 border-t pt-4">
@@ -761,6 +886,118 @@ function DeleteConfirmDialog({
   );
 }
 
+function SearchHistoryCard() {
+  const { t, i18n } = useTranslation();
+  const [history, setHistory] = useState<SearchHistory | null>(null);
+
+  const loadHistory = async () => {
+    try {
+      setHistory(await getSearchHistory());
+    } catch (error) {
+      console.error(t("settingsPage.loadSearchHistoryFailed"), error);
+    }
+  };
+
+  useEffect(() => {
+    loadHistory();
+  }, []);
+
+  const handleDelete = async (id: string) => {
+    try {
+      await deleteSearchHistoryEntry(id);
+      setHistory((prev) =>
+        prev
+          ? { ...prev, entries: prev.entries.filter((e) => e.id !== id) }
+          : prev
+      );
+    } catch (error) {
+      console.error(t("settingsPage.deleteSearchHistoryFailed"), error);
+      loadHistory();
+    }
+  };
+
+  const handleClear = async () => {
+    try {
+      await clearSearchHistory();
+      setHistory((prev) => (prev ? { ...prev, entries: [] } : prev));
+    } catch (error) {
+      console.error(t("settingsPage.deleteSearchHistoryFailed"), error);
+      loadHistory();
+    }
+  };
+
+  return (
+    <Card className="p-6">
+      <div className="space-y-4">
+        <div className="flex items-center justify-between">
+          <div>
+            <Label className="text-base font-semibold">
+              {t("settingsPage.searchHistory")}
+            </Label>
+            <p className="text-sm text-muted-foreground mt-1">
+              {history && !history.enabled
+                ? t("settingsPage.searchHistoryDisabled")
+                : history && history.retention_days > 0
+                  ? t("settingsPage.searchHistoryRetention", {
+                      days: history.retention_days,
+                    })
+                  : t("settingsPage.searchHistoryDesc")}
+            </p>
+          </div>
+          {history?.enabled && (
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={handleClear}
+              disabled={history.entries.length === 0}
+            >
+              <Trash2 className="size-4 mr-1" />
+              {t("settingsPage.clearSearchHistory")}
+            </Button>
+          )}
+        </div>
+
+        {!history ? (
+          <div className="text-sm text-muted-foreground">
+            {t("common.loading")}
+          </div>
+        ) : !history.enabled ? null : history.entries.length === 0 ? (
+          <div className="text-sm text-muted-foreground">
+            {t("settingsPage.noSearchHistory")}
+          </div>
+        ) : (
+          <ScrollArea className="h-64">
+            <div className="space-y-1 pr-3">
+              {history.entries.map((entry) => (
+                <div
+                  key={entry.id}
+                  className="flex items-center gap-3 px-3 py-2 rounded-md hover:bg-muted/50"
+                >
+                  <div className="flex-1 min-w-0">
+                    <div className="text-sm truncate">{entry.query}</div>
+                    <div className="text-xs text-muted-foreground truncate">
+                      {new Date(entry.at).toLocaleString(i18n.language)}
+                      {entry.engine && ` · ${entry.engine}`}
+                    </div>
+                  </div>
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={() => handleDelete(entry.id)}
+                    className="size-8 shrink-0"
+                  >
+                    <X className="size-4" />
+                  </Button>
+                </div>
+              ))}
+            </div>
+          </ScrollArea>
+        )}
+      </div>
+    </Card>
+  );
+}
+
 export function SettingsPage() {
   const { t } = useTranslation();
   const navigate = useNavigate();
@@ -883,8 +1120,9 @@ export function SettingsPage() {
         name: params.name,
         bookmarks: [],
         tags: [],
-        search_engine: params.searchEngine,
+        search_engines: params.searchEngines,
         intranet_check_url: params.intranetCheckUrl,
+        sort_mode: params.sortMode,
       };
       await createProfile(newProfile);
       loadProfiles();
@@ -907,8 +1145,9 @@ export function SettingsPage() {
       const updatedProfile: BookmarkProfile = {
         ...editingProfile,
         name: params.name, // 使用新名称
-        search_engine: params.searchEngine,
+        search_engines: params.searchEngines,
         intranet_check_url: params.intranetCheckUrl,
+        sort_mode: params.sortMode,
       };
       await updateProfile(updatedProfile);
     }
@@ -1015,6 +1254,8 @@ export function SettingsPage() {
             </div>
           </Card>
 
+          <SearchHistoryCard />
+
           <Card className="p-6">
             <div className="space-y-4">
               <div>
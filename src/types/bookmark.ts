@@ -7,24 +7,167 @@ export interface Bookmark {
   search_title?: string;
   url: string;
   intranet_url?: string;
+  /** 其他网络区域的链接，如 { tailscale: "http://nas.ts.net" } */
+  zone_urls?: Record<string, string>;
+  /** 由重写规则生成的链接，只在 GET 时返回，不会被保存 */
+  derived_urls?: Record<string, string>;
   search_url?: string;
+  /** 通过 /go/{keyword} 打开，在所有 profile 中唯一 */
+  keyword?: string;
   icon?: string;
+  icon_source?: IconSource;
+  health_check?: HealthCheck;
   tags: string[];
+  /** 通过 /go 打开的统计，只在 GET 时返回，不会被保存 */
+  stats?: BookmarkStats;
 }
 
-export type ImageOrientation = "Landscape" | "Portrait";
+export interface BookmarkStats {
+  count: number;
+  last_opened?: string;
+  /** 越常用、越近期打开的书签越高 */
+  frecency: number;
+  /** 当前用户在搜索中选择该书签的次数 */
+  searches: number;
+}
+
+/** "Manual" 为手动拖拽排序 */
+export type SortMode = "Manual" | "Frecency" | "MostUsed";
+
+export interface HealthCheck {
+  enabled: boolean;
+  /** "GET" 或 "HEAD" */
+  method: string;
+  /** 为空时 2xx 和 3xx 都算正常 */
+  expected_status?: number[];
+  timeout_secs?: number;
+}
+
+export type HealthState = "Up" | "Down" | "Unknown";
+
+export interface HealthResult {
+  checked_at: string;
+  up: boolean;
+  status?: number;
+  latency_ms: number;
+  error?: string;
+}
+
+export interface CertificateInfo {
+  issuer: string;
+  subject: string;
+  not_before: string;
+  not_after: string;
+  self_signed: boolean;
+}
+
+export interface TargetStatus {
+  /** 为空表示 url，否则为网络区域名称，"intranet" 表示 intranet_url */
+  zone?: string;
+  url: string;
+  history: HealthResult[];
+  certificate?: CertificateInfo;
+  state: HealthState;
+  uptime?: number;
+  /** 已过期时为负数 */
+  cert_days_until_expiry?: number;
+}
+
+export interface BookmarkStatus {
+  id: string;
+  state: HealthState;
+  /** 证书即将过期或已过期 */
+  cert_expiring: boolean;
+  targets: TargetStatus[];
+}
+
+export type IconSource = "Uploaded" | "Fetched";
+
+export type ImageOrientation = "Landscape" | "Portrait" | "Square";
+
+export interface ImageVariant {
+  filename: string;
+  format: "Avif" | "Jpeg";
+  width: number;
+  height: number;
+}
 
 export interface BackgroundImage {
   id: string;
   filename: string;
   orientation: ImageOrientation;
+  width?: number;
+  height?: number;
+  variants?: ImageVariant[];
+  blurhash?: string;
+  dominant_color?: string;
+  text_color?: string;
+  hash?: string;
 }
 
 export interface BookmarkProfile {
   name: string;
   bookmarks: Bookmark[];
   tags: string[];
-  search_engine: string;
+  search_engines: SearchEngine[];
   intranet_check_url?: string;
+  network_rules?: NetworkRules;
+  url_rewrites?: RewriteRule[];
+  /** 服务端根据 network_rules 判断的网络区域（按优先级），只在 GET 时返回 */
+  network_zones?: string[];
   background_images?: BackgroundImage[];
+  background_rotation?: BackgroundRotation;
+  /** 缺省为 "Manual" */
+  sort_mode?: SortMode;
+}
+
+export interface SearchEngine {
+  name: string;
+  /** 使用 {} 作为搜索关键词占位符 */
+  url: string;
+  /** 不含 "!"，如 "g" 对应 !g */
+  shortcut?: string;
+  suggest_url?: string;
+  /** 没有标记默认时使用第一个 */
+  default?: boolean;
+}
+
+export type RewriteRule = { zone?: string } & (
+  | { type: "host_suffix"; from: string; to: string }
+  | { type: "regex"; pattern: string; replacement: string }
+  | { type: "port_map"; host: string; target: string; scheme?: string }
+);
+
+export interface ZoneRule {
+  name: string;
+  cidrs?: string[];
+  hosts?: string[];
+}
+
+export interface NetworkRules {
+  /** 如 "192.168.1.0/24" */
+  intranet_cidrs?: string[];
+  /** 如 "nav.lan" */
+  intranet_hosts?: string[];
+  /** 其他网络区域，按顺序匹配 */
+  zones?: ZoneRule[];
+  trusted_proxies?: string[];
+}
+
+export type Weekday = "Mon" | "Tue" | "Wed" | "Thu" | "Fri" | "Sat" | "Sun";
+
+export interface BackgroundRule {
+  name?: string;
+  images: string[];
+  /** 本地时间，如 "19:00" */
+  start?: string;
+  end?: string;
+  days?: Weekday[];
+}
+
+export interface BackgroundRotation {
+  interval_minutes: number;
+  utc_offset_minutes: number;
+  rules: BackgroundRule[];
+  weights?: Record<string, number>;
 }